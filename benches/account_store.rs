@@ -0,0 +1,91 @@
+use std::hint::black_box;
+use std::sync::Arc;
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use nyu_model::accounts::{AccountStore, MemoryAccountStore};
+use nyu_model::models::Account;
+
+const ACCOUNTS: u64 = 1_000_000;
+
+fn populated_store() -> MemoryAccountStore {
+  let store = MemoryAccountStore::new();
+  for id in 1..=ACCOUNTS {
+    store.insert(Account {
+      id,
+      username: format!("user{}", id),
+      password: String::from("$argon2id$v=19$m=19456,t=2,p=1$c2FsdA$aGFzaA"),
+    });
+  }
+  store
+}
+
+fn lookups(c: &mut Criterion) {
+  let store = populated_store();
+  let mut group = c.benchmark_group("1M accounts");
+
+  let mut id = 0;
+  group.bench_function("get by id", |b| b.iter(|| {
+    id = id % ACCOUNTS + 7919;
+    black_box(store.get(id).ok())
+  }));
+
+  let usernames: Vec<String> = (1..=1000).map(|n| format!("user{}", n * 997)).collect();
+  let mut n = 0;
+  group.bench_function("get by username", |b| b.iter(|| {
+    n = (n + 1) % usernames.len();
+    black_box(store.get_by_username(&usernames[n]).ok())
+  }));
+  group.bench_function("get unknown username", |b| b.iter(|| black_box(store.get_by_username("nobody").ok())));
+
+  group.bench_function("update password", |b| b.iter(|| {
+    id = id % ACCOUNTS + 7919;
+    black_box(store.update_password(id, "rehashed").ok())
+  }));
+
+  store.list(0, 10).unwrap();
+  group.bench_function("list last page", |b| b.iter(|| black_box(store.list(ACCOUNTS as usize - 10, 10).unwrap())));
+  group.bench_function("list after a write", |b| b.iter_batched(
+    || store.insert(Account { id: 1, username: String::from("user1"), password: String::new() }),
+    |_| black_box(store.list(0, 10).unwrap()),
+    BatchSize::PerIteration,
+  ));
+
+  group.finish();
+}
+
+// Reads from several threads while one thread keeps writing, as workers do under load.
+fn contention(c: &mut Criterion) {
+  let store = Arc::new(populated_store());
+  let mut group = c.benchmark_group("1M accounts, 4 readers and 1 writer");
+  group.sample_size(20);
+
+  group.bench_function("10k lookups per reader", |b| b.iter(|| {
+    let writer = {
+      let store = store.clone();
+      thread::spawn(move || {
+        for id in 1..=1_000 {
+          store.update_password(id, "rehashed").unwrap();
+        }
+      })
+    };
+    let readers: Vec<_> = (0..4)
+      .map(|reader| {
+        let store = store.clone();
+        thread::spawn(move || {
+          for n in 0..10_000u64 {
+            black_box(store.get_by_username(&format!("user{}", (n * 31 + reader) % ACCOUNTS + 1)).ok());
+          }
+        })
+      })
+      .collect();
+
+    writer.join().unwrap();
+    readers.into_iter().for_each(|reader| reader.join().unwrap());
+  }));
+
+  group.finish();
+}
+
+criterion_group!(benches, lookups, contention);
+criterion_main!(benches);
@@ -0,0 +1,4 @@
+ALTER TABLE `access_token`
+  DROP INDEX `idx_access_token_family`,
+  DROP INDEX `idx_access_token_refresh_token`,
+  DROP `family`;
\ No newline at end of file
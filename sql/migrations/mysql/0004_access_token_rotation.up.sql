@@ -0,0 +1,4 @@
+ALTER TABLE `access_token`
+  ADD `family` VARCHAR(32) NOT NULL DEFAULT '',
+  ADD INDEX `idx_access_token_refresh_token` (`refresh_token`),
+  ADD INDEX `idx_access_token_family` (`family`);
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE `access_token`
+  DROP INDEX `idx_access_token_account`,
+  DROP INDEX `idx_access_token_access_token`,
+  DROP `revoked_at`;
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE `access_token`
+  ADD `revoked_at` DATETIME NULL DEFAULT NULL,
+  ADD INDEX `idx_access_token_access_token` (`access_token`),
+  ADD INDEX `idx_access_token_account` (`id_account`, `revoked_at`);
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE `access_token` MODIFY `access_token` VARCHAR(255) NOT NULL;
\ No newline at end of file
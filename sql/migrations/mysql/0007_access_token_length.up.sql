@@ -0,0 +1,2 @@
+-- Access tokens carry the roles and permissions of their account since 0006_roles, and outgrow 255 characters.
+ALTER TABLE `access_token` MODIFY `access_token` VARCHAR(768) NOT NULL;
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE `accounts`
+  DROP INDEX `idx_accounts_deleted_at`,
+  DROP `deleted_at`;
\ No newline at end of file
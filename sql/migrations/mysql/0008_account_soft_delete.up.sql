@@ -0,0 +1,3 @@
+ALTER TABLE `accounts`
+  ADD `deleted_at` DATETIME NULL DEFAULT NULL,
+  ADD INDEX `idx_accounts_deleted_at` (`deleted_at`);
\ No newline at end of file
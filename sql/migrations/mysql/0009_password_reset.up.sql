@@ -0,0 +1,9 @@
+CREATE TABLE `password_reset` (
+  `id` BIGINT AUTO_INCREMENT PRIMARY KEY,
+  `id_account` BIGINT NOT NULL,
+  `token_hash` CHAR(64) NOT NULL UNIQUE,
+  `expires_at` DATETIME NOT NULL,
+  `used_at` DATETIME NULL DEFAULT NULL,
+  `date_added` DATETIME DEFAULT CURRENT_TIMESTAMP,
+  INDEX `idx_password_reset_account` (`id_account`, `used_at`)
+) CHARSET=utf8mb4;
\ No newline at end of file
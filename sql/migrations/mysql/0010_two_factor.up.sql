@@ -0,0 +1,16 @@
+CREATE TABLE `account_totp` (
+  `id_account` BIGINT PRIMARY KEY,
+  `secret` VARCHAR(64) NOT NULL,
+  `enabled` TINYINT(1) NOT NULL DEFAULT 0,
+  `last_used_step` BIGINT NOT NULL DEFAULT 0,
+  `date_added` DATETIME DEFAULT CURRENT_TIMESTAMP
+) CHARSET=utf8mb4;
+
+CREATE TABLE `recovery_codes` (
+  `id` BIGINT AUTO_INCREMENT PRIMARY KEY,
+  `id_account` BIGINT NOT NULL,
+  `code_hash` CHAR(64) NOT NULL,
+  `used_at` DATETIME NULL DEFAULT NULL,
+  `date_added` DATETIME DEFAULT CURRENT_TIMESTAMP,
+  INDEX `idx_recovery_codes_account` (`id_account`, `code_hash`)
+) CHARSET=utf8mb4;
\ No newline at end of file
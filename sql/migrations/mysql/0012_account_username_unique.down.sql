@@ -0,0 +1,3 @@
+ALTER TABLE `accounts`
+  DROP INDEX `idx_accounts_active_username`,
+  DROP `active_username`;
\ No newline at end of file
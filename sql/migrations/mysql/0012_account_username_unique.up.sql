@@ -0,0 +1,3 @@
+ALTER TABLE `accounts`
+  ADD `active_username` VARCHAR(30) AS (IF(`deleted_at` IS NULL, `username`, NULL)) STORED,
+  ADD UNIQUE INDEX `idx_accounts_active_username` (`active_username`);
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE `accounts`
+  DROP INDEX `idx_accounts_updated_at`,
+  DROP `updated_at`;
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE `accounts`
+  ADD `updated_at` DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  ADD INDEX `idx_accounts_updated_at` (`updated_at`);
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TABLE accounts (
+  id BIGSERIAL PRIMARY KEY,
+  username VARCHAR(30) NOT NULL,
+  password VARCHAR(32) NOT NULL,
+  date_added TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE access_token (
+  id BIGSERIAL PRIMARY KEY,
+  id_account BIGINT NOT NULL,
+  access_token VARCHAR(255) NOT NULL,
+  refresh_token VARCHAR(255) NOT NULL,
+  date_added TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE accounts ALTER COLUMN password TYPE VARCHAR(255);
\ No newline at end of file
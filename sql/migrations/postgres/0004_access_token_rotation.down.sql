@@ -0,0 +1,4 @@
+DROP INDEX idx_access_token_family;
+DROP INDEX idx_access_token_refresh_token;
+
+ALTER TABLE access_token DROP COLUMN family;
\ No newline at end of file
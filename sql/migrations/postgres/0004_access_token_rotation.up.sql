@@ -0,0 +1,4 @@
+ALTER TABLE access_token ADD COLUMN family VARCHAR(32) NOT NULL DEFAULT '';
+
+CREATE INDEX idx_access_token_refresh_token ON access_token (refresh_token);
+CREATE INDEX idx_access_token_family ON access_token (family);
\ No newline at end of file
@@ -0,0 +1,4 @@
+DROP INDEX idx_access_token_account;
+DROP INDEX idx_access_token_access_token;
+
+ALTER TABLE access_token DROP COLUMN revoked_at;
\ No newline at end of file
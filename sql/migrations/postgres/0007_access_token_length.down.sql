@@ -0,0 +1 @@
+ALTER TABLE access_token ALTER COLUMN access_token TYPE VARCHAR(255);
\ No newline at end of file
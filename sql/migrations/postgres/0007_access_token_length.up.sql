@@ -0,0 +1,2 @@
+-- Access tokens carry the roles and permissions of their account since 0006_roles, and outgrow 255 characters.
+ALTER TABLE access_token ALTER COLUMN access_token TYPE VARCHAR(768);
\ No newline at end of file
@@ -0,0 +1,10 @@
+CREATE TABLE password_reset (
+  id BIGSERIAL PRIMARY KEY,
+  id_account BIGINT NOT NULL,
+  token_hash CHAR(64) NOT NULL UNIQUE,
+  expires_at TIMESTAMPTZ NOT NULL,
+  used_at TIMESTAMPTZ NULL DEFAULT NULL,
+  date_added TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_password_reset_account ON password_reset (id_account, used_at);
\ No newline at end of file
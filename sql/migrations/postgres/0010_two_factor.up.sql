@@ -0,0 +1,17 @@
+CREATE TABLE account_totp (
+  id_account BIGINT PRIMARY KEY,
+  secret VARCHAR(64) NOT NULL,
+  enabled BOOLEAN NOT NULL DEFAULT FALSE,
+  last_used_step BIGINT NOT NULL DEFAULT 0,
+  date_added TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE recovery_codes (
+  id BIGSERIAL PRIMARY KEY,
+  id_account BIGINT NOT NULL,
+  code_hash CHAR(64) NOT NULL,
+  used_at TIMESTAMPTZ NULL DEFAULT NULL,
+  date_added TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_recovery_codes_account ON recovery_codes (id_account, code_hash);
\ No newline at end of file
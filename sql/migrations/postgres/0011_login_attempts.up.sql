@@ -0,0 +1,8 @@
+CREATE TABLE login_attempts (
+  attempt_key VARCHAR(191) PRIMARY KEY,
+  failures INTEGER NOT NULL DEFAULT 0,
+  blocked_until TIMESTAMPTZ NOT NULL,
+  last_failure TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_login_attempts_last_failure ON login_attempts (last_failure);
\ No newline at end of file
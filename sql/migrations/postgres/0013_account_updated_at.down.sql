@@ -0,0 +1,4 @@
+DROP TRIGGER accounts_updated_at ON accounts;
+DROP FUNCTION accounts_set_updated_at();
+DROP INDEX idx_accounts_updated_at;
+ALTER TABLE accounts DROP COLUMN updated_at;
\ No newline at end of file
@@ -0,0 +1,15 @@
+ALTER TABLE accounts ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP;
+CREATE INDEX idx_accounts_updated_at ON accounts (updated_at);
+
+-- PostgreSQL has no ON UPDATE clause.
+CREATE FUNCTION accounts_set_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+  IF NEW.updated_at = OLD.updated_at THEN
+    NEW.updated_at = CURRENT_TIMESTAMP;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER accounts_updated_at BEFORE UPDATE ON accounts
+FOR EACH ROW EXECUTE FUNCTION accounts_set_updated_at();
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TABLE `accounts` (
+  `id` INTEGER PRIMARY KEY AUTOINCREMENT,
+  `username` VARCHAR(30) NOT NULL,
+  `password` VARCHAR(32) NOT NULL,
+  `date_added` DATETIME DEFAULT CURRENT_TIMESTAMP
+);
\ No newline at end of file
@@ -0,0 +1 @@
+-- SQLite does not enforce VARCHAR lengths, nothing to revert.
\ No newline at end of file
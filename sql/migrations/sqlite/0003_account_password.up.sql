@@ -0,0 +1 @@
+-- SQLite does not enforce VARCHAR lengths, `password` can already hold any hash.
\ No newline at end of file
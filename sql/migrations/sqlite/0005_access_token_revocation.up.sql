@@ -0,0 +1,3 @@
+ALTER TABLE `access_token` ADD COLUMN `revoked_at` DATETIME NULL DEFAULT NULL;
+CREATE INDEX `idx_access_token_access_token` ON `access_token` (`access_token`);
+CREATE INDEX `idx_access_token_account` ON `access_token` (`id_account`, `revoked_at`);
\ No newline at end of file
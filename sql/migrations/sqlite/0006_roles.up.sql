@@ -0,0 +1,35 @@
+CREATE TABLE `roles` (
+  `id` INTEGER PRIMARY KEY AUTOINCREMENT,
+  `name` VARCHAR(50) NOT NULL UNIQUE,
+  `date_added` DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE `permissions` (
+  `id` INTEGER PRIMARY KEY AUTOINCREMENT,
+  `name` VARCHAR(100) NOT NULL UNIQUE,
+  `date_added` DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE `role_permissions` (
+  `id_role` BIGINT NOT NULL,
+  `id_permission` BIGINT NOT NULL,
+  PRIMARY KEY (`id_role`, `id_permission`)
+);
+
+CREATE TABLE `account_roles` (
+  `id_account` BIGINT NOT NULL,
+  `id_role` BIGINT NOT NULL,
+  `date_added` DATETIME DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id_account`, `id_role`)
+);
+
+INSERT INTO `roles` (`name`) VALUES ('admin'), ('user');
+INSERT INTO `permissions` (`name`) VALUES ('accounts:read'), ('accounts:write'), ('accounts:delete'), ('accounts:manage');
+
+INSERT INTO `role_permissions` (`id_role`, `id_permission`)
+  SELECT r.id, p.id FROM `roles` r JOIN `permissions` p WHERE r.name = 'admin';
+INSERT INTO `role_permissions` (`id_role`, `id_permission`)
+  SELECT r.id, p.id FROM `roles` r JOIN `permissions` p WHERE r.name = 'user' AND p.name IN ('accounts:read', 'accounts:write');
+
+INSERT INTO `account_roles` (`id_account`, `id_role`)
+  SELECT a.id, r.id FROM `accounts` a JOIN `roles` r WHERE r.name = 'user';
\ No newline at end of file
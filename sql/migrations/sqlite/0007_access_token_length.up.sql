@@ -0,0 +1 @@
+-- SQLite does not enforce VARCHAR lengths, `access_token` can already hold tokens carrying roles and permissions.
\ No newline at end of file
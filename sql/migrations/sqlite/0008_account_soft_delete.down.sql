@@ -0,0 +1,2 @@
+DROP INDEX `idx_accounts_deleted_at`;
+ALTER TABLE `accounts` DROP COLUMN `deleted_at`;
\ No newline at end of file
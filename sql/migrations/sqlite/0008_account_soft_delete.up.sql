@@ -0,0 +1,2 @@
+ALTER TABLE `accounts` ADD COLUMN `deleted_at` DATETIME NULL DEFAULT NULL;
+CREATE INDEX `idx_accounts_deleted_at` ON `accounts` (`deleted_at`);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE `password_reset`;
\ No newline at end of file
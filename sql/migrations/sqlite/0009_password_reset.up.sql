@@ -0,0 +1,9 @@
+CREATE TABLE `password_reset` (
+  `id` INTEGER PRIMARY KEY AUTOINCREMENT,
+  `id_account` BIGINT NOT NULL,
+  `token_hash` CHAR(64) NOT NULL UNIQUE,
+  `expires_at` DATETIME NOT NULL,
+  `used_at` DATETIME NULL DEFAULT NULL,
+  `date_added` DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX `idx_password_reset_account` ON `password_reset` (`id_account`, `used_at`);
\ No newline at end of file
@@ -0,0 +1,2 @@
+DROP TABLE `recovery_codes`;
+DROP TABLE `account_totp`;
\ No newline at end of file
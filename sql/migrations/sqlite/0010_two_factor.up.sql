@@ -0,0 +1,16 @@
+CREATE TABLE `account_totp` (
+  `id_account` BIGINT PRIMARY KEY,
+  `secret` VARCHAR(64) NOT NULL,
+  `enabled` TINYINT(1) NOT NULL DEFAULT 0,
+  `last_used_step` BIGINT NOT NULL DEFAULT 0,
+  `date_added` DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE `recovery_codes` (
+  `id` INTEGER PRIMARY KEY AUTOINCREMENT,
+  `id_account` BIGINT NOT NULL,
+  `code_hash` CHAR(64) NOT NULL,
+  `used_at` DATETIME NULL DEFAULT NULL,
+  `date_added` DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX `idx_recovery_codes_account` ON `recovery_codes` (`id_account`, `code_hash`);
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE `login_attempts` (
+  `attempt_key` VARCHAR(191) PRIMARY KEY,
+  `failures` INT UNSIGNED NOT NULL DEFAULT 0,
+  `blocked_until` DATETIME NOT NULL,
+  `last_failure` DATETIME NOT NULL
+);
+CREATE INDEX `idx_login_attempts_last_failure` ON `login_attempts` (`last_failure`);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP INDEX `idx_accounts_active_username`;
\ No newline at end of file
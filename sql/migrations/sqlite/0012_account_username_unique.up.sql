@@ -0,0 +1 @@
+CREATE UNIQUE INDEX `idx_accounts_active_username` ON `accounts` (`username`) WHERE `deleted_at` IS NULL;
\ No newline at end of file
@@ -0,0 +1,4 @@
+DROP TRIGGER `accounts_updated_at_update`;
+DROP TRIGGER `accounts_updated_at_insert`;
+DROP INDEX `idx_accounts_updated_at`;
+ALTER TABLE `accounts` DROP COLUMN `updated_at`;
\ No newline at end of file
@@ -0,0 +1,15 @@
+ALTER TABLE `accounts` ADD COLUMN `updated_at` DATETIME NOT NULL DEFAULT '1970-01-01 00:00:00';
+UPDATE `accounts` SET `updated_at` = CURRENT_TIMESTAMP;
+CREATE INDEX `idx_accounts_updated_at` ON `accounts` (`updated_at`);
+
+-- SQLite has no ON UPDATE clause, nor non-constant defaults for added columns.
+CREATE TRIGGER `accounts_updated_at_insert` AFTER INSERT ON `accounts`
+BEGIN
+  UPDATE `accounts` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
+
+CREATE TRIGGER `accounts_updated_at_update` AFTER UPDATE ON `accounts`
+WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+  UPDATE `accounts` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
\ No newline at end of file
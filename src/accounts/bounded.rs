@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use lru::LruCache;
+use crate::models::Account;
+
+struct Entry {
+  account: Account,
+  loaded_at: Instant,
+}
+
+struct Entries {
+  by_id: LruCache<u64, Entry>,
+  by_username: HashMap<String, u64>,
+}
+
+impl Entries {
+  fn unindex(&mut self, id_account: u64, username: &str) {
+    if self.by_username.get(username) == Some(&id_account) {
+      self.by_username.remove(username);
+    }
+  }
+
+  fn take(&mut self, id_account: u64) -> Option<Entry> {
+    let entry = self.by_id.pop(&id_account)?;
+    self.unindex(id_account, &entry.account.username);
+    Some(entry)
+  }
+}
+
+// Keeps the most recently used accounts, up to `capacity`, for at most `ttl`. Accounts dropped
+// for either reason are counted as evictions.
+pub struct BoundedAccountCache {
+  entries: Mutex<Entries>,
+  ttl: Duration,
+  evictions: AtomicU64,
+}
+
+impl BoundedAccountCache {
+  pub fn new(capacity: NonZeroUsize, ttl: Duration) -> BoundedAccountCache {
+    BoundedAccountCache {
+      entries: Mutex::new(Entries { by_id: LruCache::new(capacity), by_username: HashMap::new() }),
+      ttl,
+      evictions: AtomicU64::new(0),
+    }
+  }
+
+  pub fn get(&self, id_account: u64) -> Option<Account> {
+    let mut entries = self.entries.lock().unwrap();
+
+    let entry = entries.by_id.get(&id_account)?;
+    if entry.loaded_at.elapsed() < self.ttl {
+      return Some(entry.account.clone());
+    }
+
+    entries.take(id_account);
+    self.evictions.fetch_add(1, Ordering::Relaxed);
+    None
+  }
+
+  pub fn get_by_username(&self, username: &str) -> Option<Account> {
+    let id_account = *self.entries.lock().unwrap().by_username.get(username)?;
+    self.get(id_account)
+  }
+
+  // Adds the account, or replaces the one with the same id. An entry still holding the username
+  // is stale, and dropped.
+  pub fn insert(&self, account: Account) {
+    let mut entries = self.entries.lock().unwrap();
+
+    if let Some(previous) = entries.by_id.peek(&account.id) {
+      let username = previous.account.username.clone();
+      entries.unindex(account.id, &username);
+    }
+    if let Some(&holder) = entries.by_username.get(&account.username) {
+      if holder != account.id {
+        entries.take(holder);
+      }
+    }
+
+    let id_account = account.id;
+    entries.by_username.insert(account.username.clone(), id_account);
+    let entry = Entry { account, loaded_at: Instant::now() };
+    if let Some((evicted, entry)) = entries.by_id.push(id_account, entry) {
+      if evicted != id_account {
+        entries.unindex(evicted, &entry.account.username);
+        self.evictions.fetch_add(1, Ordering::Relaxed);
+      }
+    }
+  }
+
+  pub fn remove(&self, id_account: u64) {
+    self.entries.lock().unwrap().take(id_account);
+  }
+
+  // Drops the account, and any other entry holding its username, so both are read again.
+  pub fn invalidate(&self, account: &Account) {
+    let mut entries = self.entries.lock().unwrap();
+
+    entries.take(account.id);
+    if let Some(&holder) = entries.by_username.get(&account.username) {
+      entries.take(holder);
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.lock().unwrap().by_id.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn evictions(&self) -> u64 {
+    self.evictions.load(Ordering::Relaxed)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn account(id: u64, username: &str) -> Account {
+    Account { id, username: String::from(username), password: String::from("hash") }
+  }
+
+  fn cache(capacity: usize) -> BoundedAccountCache {
+    BoundedAccountCache::new(NonZeroUsize::new(capacity).unwrap(), Duration::from_secs(60))
+  }
+
+  #[test]
+  fn least_recently_used_accounts_are_evicted_first() {
+    let cache = cache(2);
+    cache.insert(account(1, "alice"));
+    cache.insert(account(2, "bob"));
+    cache.get(1).unwrap();
+    cache.insert(account(3, "carol"));
+
+    assert!(cache.get(2).is_none());
+    assert!(cache.get_by_username("bob").is_none());
+    assert_eq!(cache.get_by_username("alice").unwrap().id, 1);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.evictions(), 1);
+  }
+
+  #[test]
+  fn expired_accounts_are_not_returned() {
+    let cache = BoundedAccountCache::new(NonZeroUsize::new(10).unwrap(), Duration::ZERO);
+    cache.insert(account(1, "alice"));
+
+    assert!(cache.get(1).is_none());
+    assert!(cache.is_empty());
+    assert_eq!(cache.evictions(), 1);
+  }
+
+  #[test]
+  fn usernames_follow_renames() {
+    let cache = cache(10);
+    cache.insert(account(1, "alice"));
+    cache.insert(account(1, "alicia"));
+    cache.insert(account(2, "alice"));
+
+    assert_eq!(cache.get_by_username("alice").unwrap().id, 2);
+    assert_eq!(cache.get_by_username("alicia").unwrap().id, 1);
+
+    // Account 3 now holds the username cached for account 2.
+    cache.invalidate(&account(3, "alice"));
+    assert!(cache.get(2).is_none());
+    assert_eq!(cache.evictions(), 0);
+  }
+}
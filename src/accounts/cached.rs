@@ -0,0 +1,522 @@
+use std::env;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use actix_web::{rt, web};
+use crate::{models::Account, errors::ActixError};
+use super::{AccountChange, AccountChangeFeed, AccountEvents, AccountStore, BoundedAccountCache, MemoryAccountStore};
+
+// Changes are read again for this long, so that a write committed just after a refresh, but
+// stamped just before it, is not missed.
+const REFRESH_OVERLAP_SECONDS: i64 = 5;
+
+enum Cache {
+  // Every active account, so a miss means the account does not exist.
+  Full(MemoryAccountStore),
+  // Recently used accounts only. Misses are looked up in `inner`.
+  Bounded(BoundedAccountCache),
+}
+
+pub struct CacheStats {
+  pub hits: u64,
+  pub misses: u64,
+  pub evictions: u64,
+  pub size: usize,
+}
+
+// How the account cache of the service is built and kept in sync with other instances.
+pub struct CacheSettings {
+  pub mode: CacheMode,
+  pub refresh_period: Duration,
+}
+
+pub enum CacheMode {
+  // Every account is loaded at startup.
+  Eager,
+  // Accounts are loaded when first read, see `CachedAccountStore::lazy`.
+  Lazy { capacity: NonZeroUsize, ttl: Duration },
+}
+
+impl CacheSettings {
+  // `ACCOUNT_CACHE` is `lazy` (default) or `eager`. Lazy caches read `ACCOUNT_CACHE_CAPACITY` and
+  // `ACCOUNT_CACHE_TTL_SECONDS`; both modes read `ACCOUNT_REFRESH_SECONDS`.
+  pub fn from_env() -> Result<Self, String> {
+    let mode = match env::var("ACCOUNT_CACHE").as_deref() {
+      Ok("eager") => CacheMode::Eager,
+      Ok("lazy") | Err(_) => CacheMode::Lazy {
+        capacity: read_env("ACCOUNT_CACHE_CAPACITY", NonZeroUsize::new(100_000).unwrap())?,
+        ttl: Duration::from_secs(read_env("ACCOUNT_CACHE_TTL_SECONDS", 300)?),
+      },
+      Ok(mode) => return Err(format!("ACCOUNT_CACHE doit valoir eager ou lazy, pas {}", mode)),
+    };
+
+    Ok(CacheSettings {
+      mode,
+      refresh_period: Duration::from_secs(read_env("ACCOUNT_REFRESH_SECONDS", 5)?),
+    })
+  }
+}
+
+fn read_env<T: FromStr>(name: &str, default: T) -> Result<T, String> {
+  match env::var(name) {
+    Ok(value) => value.parse().map_err(|_| format!("{} doit être un nombre positif, pas {}", name, value)),
+    Err(_) => Ok(default),
+  }
+}
+
+// Serves reads from memory and writes through to `inner`. The cache is only touched once
+// `inner` accepted the write, so a failing write leaves both unchanged. Username checks against
+// the cache are a shortcut; `inner` has the final say.
+pub struct CachedAccountStore<S> {
+  inner: S,
+  cache: Cache,
+  events: Option<Arc<dyn AccountEvents>>,
+  hits: AtomicU64,
+  misses: AtomicU64,
+}
+
+impl<S: AccountStore> CachedAccountStore<S> {
+  fn new(inner: S, cache: Cache) -> Self {
+    CachedAccountStore { inner, cache, events: None, hits: AtomicU64::new(0), misses: AtomicU64::new(0) }
+  }
+
+  // Preloads every account of `inner`.
+  pub fn load(inner: S) -> Result<Self, ActixError> {
+    let cache = MemoryAccountStore::new();
+    for account in inner.list(0, usize::MAX)? {
+      cache.insert(account);
+    }
+
+    Ok(CachedAccountStore::new(inner, Cache::Full(cache)))
+  }
+
+  // Loads accounts from `inner` when first read, and keeps at most `capacity` of them for `ttl`.
+  // Listings always go to `inner`.
+  pub fn lazy(inner: S, capacity: NonZeroUsize, ttl: Duration) -> Self {
+    CachedAccountStore::new(inner, Cache::Bounded(BoundedAccountCache::new(capacity, ttl)))
+  }
+
+  // Other instances are told about every write, on top of their periodic refresh.
+  pub fn with_events(mut self, events: Arc<dyn AccountEvents>) -> Self {
+    self.events = Some(events);
+    self
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    let (evictions, size) = match &self.cache {
+      Cache::Full(cache) => (0, cache.len()),
+      Cache::Bounded(cache) => (cache.evictions(), cache.len()),
+    };
+
+    CacheStats {
+      hits: self.hits.load(Ordering::Relaxed),
+      misses: self.misses.load(Ordering::Relaxed),
+      evictions,
+      size,
+    }
+  }
+
+  pub fn apply(&self, changes: Vec<AccountChange>) {
+    for change in changes {
+      match (change, &self.cache) {
+        (AccountChange::Saved(account), Cache::Full(cache)) => cache.insert(account),
+        // Only accounts already cached need to be read again, on their next use.
+        (AccountChange::Saved(account), Cache::Bounded(cache)) => cache.invalidate(&account),
+        (AccountChange::Deleted(id_account), _) => self.remove(id_account),
+      }
+    }
+  }
+
+  // Replaces the cached account by the current one from `inner`.
+  pub fn reload(&self, id_account: u64) -> Result<(), ActixError> {
+    if let Cache::Bounded(cache) = &self.cache {
+      cache.remove(id_account);
+      return Ok(());
+    }
+
+    match self.inner.get(id_account) {
+      Ok(account) => self.insert(account),
+      Err(ActixError::NotFound) => self.remove(id_account),
+      Err(err) => return Err(err),
+    }
+    Ok(())
+  }
+
+  fn insert(&self, account: Account) {
+    match &self.cache {
+      Cache::Full(cache) => cache.insert(account),
+      Cache::Bounded(cache) => cache.insert(account),
+    }
+  }
+
+  fn remove(&self, id_account: u64) {
+    match &self.cache {
+      Cache::Full(cache) => {
+        cache.remove(id_account);
+      }
+      Cache::Bounded(cache) => cache.remove(id_account),
+    }
+  }
+
+  fn cached(&self, account: Option<Account>) -> Option<Account> {
+    let counter = if account.is_some() { &self.hits } else { &self.misses };
+    counter.fetch_add(1, Ordering::Relaxed);
+    account
+  }
+
+  // Only a full cache knows every username in use.
+  fn is_taken(&self, username: &str, id_account: Option<u64>) -> bool {
+    match &self.cache {
+      Cache::Full(cache) => cache.get_by_username(username).is_ok_and(|existing| Some(existing.id) != id_account),
+      Cache::Bounded(_) => false,
+    }
+  }
+
+  // Caches the account as `inner` stored it, or forgets it when `inner` no longer has it.
+  fn updated(&self, id_account: u64, updated: Result<Account, ActixError>) -> Result<Account, ActixError> {
+    match updated {
+      Ok(account) => {
+        self.insert(account.clone());
+        self.published(id_account);
+        Ok(account)
+      }
+      Err(ActixError::NotFound) => {
+        self.remove(id_account);
+        Err(ActixError::NotFound)
+      }
+      Err(err) => Err(err),
+    }
+  }
+
+  fn published(&self, id_account: u64) {
+    if let Some(events) = &self.events {
+      events.publish(id_account);
+    }
+  }
+}
+
+impl<S: AccountStore + 'static> CachedAccountStore<S> {
+  // Logs the counters of `stats` every `period`, at the info level.
+  pub fn spawn_stats_report(self: Arc<Self>, period: Duration) {
+    rt::spawn(async move {
+      let mut interval = rt::time::interval(period);
+      interval.tick().await;
+
+      loop {
+        interval.tick().await;
+        let stats = self.stats();
+        log::info!(
+          "Cache des comptes: {} comptes, {} succès, {} échecs, {} évictions",
+          stats.size, stats.hits, stats.misses, stats.evictions,
+        );
+      }
+    });
+  }
+}
+
+impl<S: AccountStore + AccountChangeFeed + 'static> CachedAccountStore<S> {
+  // Applies the changes made since `since`, every `period`. Writes from other instances are
+  // visible here after at most `period` plus the time a refresh takes.
+  pub fn spawn_refresh(self: Arc<Self>, since: i64, period: Duration) {
+    rt::spawn(async move {
+      let mut since = since;
+      let mut interval = rt::time::interval(period);
+      interval.tick().await;
+
+      loop {
+        interval.tick().await;
+        let store = self.clone();
+        let refreshed = web::block(move || {
+          let (changes, now) = store.inner.changes_since(since - REFRESH_OVERLAP_SECONDS)?;
+          store.apply(changes);
+          Ok::<_, ActixError>(now)
+        }).await;
+
+        match refreshed {
+          Ok(Ok(now)) => since = now,
+          Ok(Err(err)) => eprintln!("Erreur lors de la synchronisation des comptes: {}", err),
+          Err(err) => eprintln!("Erreur lors de la synchronisation des comptes: {}", err),
+        }
+      }
+    });
+  }
+}
+
+impl<S: AccountStore> AccountStore for CachedAccountStore<S> {
+  fn create(&self, username: &str, hash_password: &str) -> Result<Account, ActixError> {
+    if self.is_taken(username, None) {
+      return Err(ActixError::SameAccountName);
+    }
+
+    let account = self.inner.create(username, hash_password)?;
+    self.insert(account.clone());
+    self.published(account.id);
+
+    Ok(account)
+  }
+
+  fn get(&self, id_account: u64) -> Result<Account, ActixError> {
+    match &self.cache {
+      Cache::Full(cache) => self.cached(cache.get(id_account).ok()).ok_or(ActixError::NotFound),
+      Cache::Bounded(cache) => match self.cached(cache.get(id_account)) {
+        Some(account) => Ok(account),
+        None => {
+          let account = self.inner.get(id_account)?;
+          cache.insert(account.clone());
+          Ok(account)
+        }
+      },
+    }
+  }
+
+  fn get_by_username(&self, username: &str) -> Result<Account, ActixError> {
+    match &self.cache {
+      Cache::Full(cache) => self.cached(cache.get_by_username(username).ok()).ok_or(ActixError::NotFound),
+      Cache::Bounded(cache) => match self.cached(cache.get_by_username(username)) {
+        Some(account) => Ok(account),
+        None => {
+          let account = self.inner.get_by_username(username)?;
+          cache.insert(account.clone());
+          Ok(account)
+        }
+      },
+    }
+  }
+
+  fn list(&self, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError> {
+    match &self.cache {
+      Cache::Full(cache) => cache.list(offset, limit),
+      Cache::Bounded(_) => self.inner.list(offset, limit),
+    }
+  }
+
+  fn update_username(&self, id_account: u64, username: &str) -> Result<Account, ActixError> {
+    if self.is_taken(username, Some(id_account)) {
+      return Err(ActixError::SameAccountName);
+    }
+
+    self.updated(id_account, self.inner.update_username(id_account, username))
+  }
+
+  fn update_password(&self, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+    self.updated(id_account, self.inner.update_password(id_account, hash_password))
+  }
+
+  fn delete(&self, id_account: u64) -> Result<(), ActixError> {
+    self.inner.delete(id_account)?;
+    self.remove(id_account);
+    self.published(id_account);
+
+    Ok(())
+  }
+
+  fn restore(&self, id_account: u64) -> Result<Account, ActixError> {
+    let account = self.inner.restore(id_account)?;
+    self.insert(account.clone());
+    self.published(id_account);
+
+    Ok(account)
+  }
+
+  fn refresh(&self, id_account: u64) -> Result<(), ActixError> {
+    self.reload(id_account)?;
+    self.published(id_account);
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicBool, Ordering};
+
+  // Delegates to a memory store, but refuses writes while `failing` is set.
+  #[derive(Default)]
+  struct FlakyStore {
+    store: MemoryAccountStore,
+    failing: AtomicBool,
+  }
+
+  impl FlakyStore {
+    fn check(&self) -> Result<(), ActixError> {
+      if self.failing.load(Ordering::Relaxed) {
+        Err(ActixError::Unknown)
+      } else {
+        Ok(())
+      }
+    }
+  }
+
+  impl AccountStore for &FlakyStore {
+    fn create(&self, username: &str, hash_password: &str) -> Result<Account, ActixError> {
+      self.check()?;
+      self.store.create(username, hash_password)
+    }
+
+    fn get(&self, id_account: u64) -> Result<Account, ActixError> {
+      self.store.get(id_account)
+    }
+
+    fn get_by_username(&self, username: &str) -> Result<Account, ActixError> {
+      self.store.get_by_username(username)
+    }
+
+    fn list(&self, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError> {
+      self.store.list(offset, limit)
+    }
+
+    fn update_username(&self, id_account: u64, username: &str) -> Result<Account, ActixError> {
+      self.check()?;
+      self.store.update_username(id_account, username)
+    }
+
+    fn update_password(&self, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+      self.check()?;
+      self.store.update_password(id_account, hash_password)
+    }
+
+    fn delete(&self, id_account: u64) -> Result<(), ActixError> {
+      self.check()?;
+      self.store.delete(id_account)
+    }
+
+    fn restore(&self, id_account: u64) -> Result<Account, ActixError> {
+      self.check()?;
+      self.store.restore(id_account)
+    }
+  }
+
+  #[test]
+  fn preloads_the_inner_store() {
+    let inner = FlakyStore::default();
+    let alice = inner.store.create("alice", "hash").unwrap();
+
+    let cached = CachedAccountStore::load(&inner).unwrap();
+    assert_eq!(cached.get_by_username("alice").unwrap().id, alice.id);
+  }
+
+  #[test]
+  fn writes_reach_both_the_inner_store_and_the_cache() {
+    let inner = FlakyStore::default();
+    let cached = CachedAccountStore::load(&inner).unwrap();
+
+    let alice = cached.create("alice", "hash").unwrap();
+    cached.update_username(alice.id, "alicia").unwrap();
+
+    assert_eq!(inner.store.get(alice.id).unwrap().username, "alicia");
+    assert_eq!(cached.get(alice.id).unwrap().username, "alicia");
+
+    cached.delete(alice.id).unwrap();
+    assert!(inner.store.get(alice.id).is_err());
+    assert!(cached.get(alice.id).is_err());
+  }
+
+  #[test]
+  fn conflicts_reported_by_the_inner_store_leave_the_cache_untouched() {
+    let inner = FlakyStore::default();
+    let cached = CachedAccountStore::load(&inner).unwrap();
+    let alice = cached.create("alice", "hash").unwrap();
+    // Created behind the cache's back, as another instance would.
+    inner.store.create("bob", "hash").unwrap();
+
+    assert!(matches!(cached.update_username(alice.id, "bob"), Err(ActixError::SameAccountName)));
+    assert_eq!(cached.get(alice.id).unwrap().username, "alice");
+  }
+
+  #[test]
+  fn updating_an_account_deleted_elsewhere_drops_it_from_the_cache() {
+    let inner = FlakyStore::default();
+    let cached = CachedAccountStore::load(&inner).unwrap();
+    let alice = cached.create("alice", "hash").unwrap();
+    inner.store.delete(alice.id).unwrap();
+
+    assert!(matches!(cached.update_password(alice.id, "rehashed"), Err(ActixError::NotFound)));
+    assert!(matches!(cached.get(alice.id), Err(ActixError::NotFound)));
+    assert!(cached.list(0, 10).unwrap().is_empty());
+  }
+
+  #[test]
+  fn changes_from_other_instances_are_applied() {
+    let inner = FlakyStore::default();
+    let alice = inner.store.create("alice", "hash").unwrap();
+    let cached = CachedAccountStore::load(&inner).unwrap();
+
+    // Elsewhere, alice is renamed and her old username is given to a new account.
+    let renamed = Account { username: String::from("alicia"), ..alice.clone() };
+    let bob = Account { id: alice.id + 1, username: String::from("alice"), password: String::from("hash") };
+    cached.apply(vec![AccountChange::Saved(bob.clone()), AccountChange::Saved(renamed)]);
+
+    assert_eq!(cached.get_by_username("alice").unwrap().id, bob.id);
+    assert_eq!(cached.get_by_username("alicia").unwrap().id, alice.id);
+
+    cached.apply(vec![AccountChange::Deleted(bob.id)]);
+    assert!(cached.get(bob.id).is_err());
+    assert!(cached.get_by_username("alice").is_err());
+  }
+
+  #[test]
+  fn reload_follows_the_inner_store() {
+    let inner = FlakyStore::default();
+    let cached = CachedAccountStore::load(&inner).unwrap();
+    let alice = inner.store.create("alice", "hash").unwrap();
+
+    cached.reload(alice.id).unwrap();
+    assert_eq!(cached.get(alice.id).unwrap().username, "alice");
+
+    inner.store.delete(alice.id).unwrap();
+    cached.reload(alice.id).unwrap();
+    assert!(cached.get(alice.id).is_err());
+  }
+
+  fn lazy(inner: &FlakyStore) -> CachedAccountStore<&FlakyStore> {
+    CachedAccountStore::lazy(inner, NonZeroUsize::new(10).unwrap(), Duration::from_secs(60))
+  }
+
+  #[test]
+  fn lazy_stores_load_accounts_on_first_read() {
+    let inner = FlakyStore::default();
+    let alice = inner.store.create("alice", "hash").unwrap();
+    let cached = lazy(&inner);
+    assert_eq!(cached.stats().size, 0);
+
+    assert_eq!(cached.get_by_username("alice").unwrap().id, alice.id);
+    assert_eq!(cached.get(alice.id).unwrap().username, "alice");
+    assert!(matches!(cached.get(alice.id + 1), Err(ActixError::NotFound)));
+
+    let stats = cached.stats();
+    assert_eq!((stats.hits, stats.misses, stats.size), (1, 2, 1));
+    assert_eq!(cached.list(0, 10).unwrap().len(), 1);
+  }
+
+  #[test]
+  fn lazy_stores_read_changed_accounts_again() {
+    let inner = FlakyStore::default();
+    let alice = inner.store.create("alice", "hash").unwrap();
+    let cached = lazy(&inner);
+    cached.get(alice.id).unwrap();
+
+    let renamed = inner.store.update_username(alice.id, "alicia").unwrap();
+    cached.apply(vec![AccountChange::Saved(renamed)]);
+
+    assert_eq!(cached.get(alice.id).unwrap().username, "alicia");
+    assert!(cached.get_by_username("alice").is_err());
+  }
+
+  #[test]
+  fn failed_writes_leave_the_cache_untouched() {
+    let inner = FlakyStore::default();
+    let cached = CachedAccountStore::load(&inner).unwrap();
+    let alice = cached.create("alice", "hash").unwrap();
+
+    inner.failing.store(true, Ordering::Relaxed);
+    assert!(cached.update_username(alice.id, "alicia").is_err());
+    assert!(cached.delete(alice.id).is_err());
+    assert!(cached.create("bob", "hash").is_err());
+
+    assert_eq!(cached.get(alice.id).unwrap().username, "alice");
+    assert!(cached.get_by_username("bob").is_err());
+  }
+}
@@ -0,0 +1,88 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use super::{AccountStore, CachedAccountStore};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(200);
+const IO_TIMEOUT: Duration = Duration::from_millis(200);
+// A lost subscription is opened again after this delay.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+// Tells other instances that an account changed, so they need not wait for their next refresh.
+// Delivery is best effort: a missed event is caught up by the refresh.
+pub trait AccountEvents: Send + Sync {
+  fn publish(&self, id_account: u64);
+}
+
+pub struct RedisAccountEvents {
+  client: redis::Client,
+  channel: String,
+  connection: Mutex<Option<redis::Connection>>,
+}
+
+impl RedisAccountEvents {
+  pub fn new(url: &str) -> Result<Self, String> {
+    let client = redis::Client::open(url).map_err(|err| err.to_string())?;
+
+    Ok(RedisAccountEvents {
+      client,
+      channel: String::from("account_events"),
+      connection: Mutex::new(None),
+    })
+  }
+
+  fn connect(&self) -> Result<redis::Connection, redis::RedisError> {
+    let connection = self.client.get_connection_with_timeout(CONNECT_TIMEOUT)?;
+    connection.set_read_timeout(Some(IO_TIMEOUT))?;
+    connection.set_write_timeout(Some(IO_TIMEOUT))?;
+    Ok(connection)
+  }
+
+  // Reloads every account published by any instance, this one included, from a dedicated thread.
+  pub fn subscribe<S: AccountStore + 'static>(&self, store: Arc<CachedAccountStore<S>>) {
+    let client = self.client.clone();
+    let channel = self.channel.clone();
+
+    thread::spawn(move || loop {
+      if let Err(err) = listen(&client, &channel, &store) {
+        eprintln!("Erreur lors de l'écoute des modifications de comptes: {}", err);
+      }
+      thread::sleep(RETRY_DELAY);
+    });
+  }
+}
+
+fn listen<S: AccountStore>(client: &redis::Client, channel: &str, store: &CachedAccountStore<S>) -> Result<(), redis::RedisError> {
+  let mut connection = client.get_connection_with_timeout(CONNECT_TIMEOUT)?;
+  let mut pubsub = connection.as_pubsub();
+  pubsub.subscribe(channel)?;
+
+  loop {
+    let payload: String = pubsub.get_message()?.get_payload()?;
+    let Ok(id_account) = payload.parse() else {
+      continue;
+    };
+    if let Err(err) = store.reload(id_account) {
+      eprintln!("Erreur lors du rechargement du compte {}: {}", id_account, err);
+    }
+  }
+}
+
+impl AccountEvents for RedisAccountEvents {
+  fn publish(&self, id_account: u64) {
+    let mut connection = self.connection.lock().unwrap();
+    let published = match connection.as_mut() {
+      Some(connection) => redis::cmd("PUBLISH").arg(&self.channel).arg(id_account).exec(connection),
+      None => self.connect().and_then(|mut new_connection| {
+        redis::cmd("PUBLISH").arg(&self.channel).arg(id_account).exec(&mut new_connection)?;
+        *connection = Some(new_connection);
+        Ok(())
+      }),
+    };
+
+    if let Err(err) = published {
+      *connection = None;
+      eprintln!("Erreur lors de la publication de la modification du compte {}: {}", id_account, err);
+    }
+  }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use crate::{models::Account, errors::ActixError};
+use super::AccountStore;
+
+#[derive(Default)]
+struct Accounts {
+  by_id: HashMap<u64, Arc<Account>>,
+  by_username: HashMap<String, u64>,
+  deleted: HashMap<u64, Account>,
+  last_id: u64,
+  // Active accounts ordered by id, rebuilt by the first listing after a write.
+  snapshot: Option<Arc<Vec<Arc<Account>>>>,
+}
+
+impl Accounts {
+  fn get_by_username(&self, username: &str) -> Option<&Arc<Account>> {
+    self.by_username.get(username).and_then(|id| self.by_id.get(id))
+  }
+
+  fn is_taken(&self, username: &str, id_account: u64) -> bool {
+    self.get_by_username(username).is_some_and(|account| account.id != id_account)
+  }
+
+  fn put(&mut self, account: Account) {
+    if let Some(previous) = self.by_id.get(&account.id) {
+      if previous.username != account.username && self.by_username.get(&previous.username) == Some(&account.id) {
+        self.by_username.remove(&previous.username);
+      }
+    }
+
+    self.last_id = self.last_id.max(account.id);
+    self.by_username.insert(account.username.clone(), account.id);
+    self.by_id.insert(account.id, Arc::new(account));
+    self.snapshot = None;
+  }
+
+  fn take(&mut self, id_account: u64) -> Option<Account> {
+    let account = self.by_id.remove(&id_account)?;
+    if self.by_username.get(&account.username) == Some(&id_account) {
+      self.by_username.remove(&account.username);
+    }
+    self.snapshot = None;
+
+    Some(Arc::unwrap_or_clone(account))
+  }
+}
+
+// Keeps accounts in memory only, indexed by id and by username. Used on its own in tests, and as
+// the cache of `CachedAccountStore`.
+#[derive(Default)]
+pub struct MemoryAccountStore {
+  accounts: RwLock<Accounts>,
+}
+
+impl MemoryAccountStore {
+  pub fn new() -> MemoryAccountStore {
+    MemoryAccountStore::default()
+  }
+
+  // Adds the account, or replaces the one with the same id.
+  pub fn insert(&self, account: Account) {
+    self.accounts.write().unwrap().put(account);
+  }
+
+  pub fn remove(&self, id_account: u64) -> Option<Account> {
+    self.accounts.write().unwrap().take(id_account)
+  }
+
+  // Active accounts only.
+  pub fn len(&self) -> usize {
+    self.accounts.read().unwrap().by_id.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  // Listings page through a shared snapshot, so they neither copy every account nor hold the
+  // lock while doing so.
+  fn snapshot(&self) -> Arc<Vec<Arc<Account>>> {
+    if let Some(snapshot) = &self.accounts.read().unwrap().snapshot {
+      return snapshot.clone();
+    }
+
+    let mut accounts = self.accounts.write().unwrap();
+    if let Some(snapshot) = &accounts.snapshot {
+      return snapshot.clone();
+    }
+
+    let mut ordered: Vec<Arc<Account>> = accounts.by_id.values().cloned().collect();
+    ordered.sort_unstable_by_key(|account| account.id);
+    let snapshot = Arc::new(ordered);
+    accounts.snapshot = Some(snapshot.clone());
+
+    snapshot
+  }
+}
+
+impl AccountStore for MemoryAccountStore {
+  fn create(&self, username: &str, hash_password: &str) -> Result<Account, ActixError> {
+    let mut accounts = self.accounts.write().unwrap();
+    if accounts.by_username.contains_key(username) {
+      return Err(ActixError::SameAccountName);
+    }
+
+    let account = Account {
+      id: accounts.last_id + 1,
+      username: username.to_string(),
+      password: hash_password.to_string(),
+    };
+    accounts.put(account.clone());
+
+    Ok(account)
+  }
+
+  fn get(&self, id_account: u64) -> Result<Account, ActixError> {
+    let accounts = self.accounts.read().unwrap();
+
+    match accounts.by_id.get(&id_account) {
+      Some(account) => Ok(Account::clone(account)),
+      None => Err(ActixError::NotFound),
+    }
+  }
+
+  fn get_by_username(&self, username: &str) -> Result<Account, ActixError> {
+    let accounts = self.accounts.read().unwrap();
+
+    match accounts.get_by_username(username) {
+      Some(account) => Ok(Account::clone(account)),
+      None => Err(ActixError::NotFound),
+    }
+  }
+
+  fn list(&self, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError> {
+    let snapshot = self.snapshot();
+    let page = snapshot.get(offset..).unwrap_or_default();
+
+    Ok(page.iter().take(limit).map(|account| Account::clone(account)).collect())
+  }
+
+  fn update_username(&self, id_account: u64, username: &str) -> Result<Account, ActixError> {
+    let mut accounts = self.accounts.write().unwrap();
+    let mut account = accounts.by_id.get(&id_account).map(|account| Account::clone(account)).ok_or(ActixError::NotFound)?;
+    if accounts.is_taken(username, id_account) {
+      return Err(ActixError::SameAccountName);
+    }
+
+    account.username = username.to_string();
+    accounts.put(account.clone());
+    Ok(account)
+  }
+
+  fn update_password(&self, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+    let mut accounts = self.accounts.write().unwrap();
+    let mut account = accounts.by_id.get(&id_account).map(|account| Account::clone(account)).ok_or(ActixError::NotFound)?;
+
+    account.password = hash_password.to_string();
+    accounts.put(account.clone());
+    Ok(account)
+  }
+
+  fn delete(&self, id_account: u64) -> Result<(), ActixError> {
+    let mut accounts = self.accounts.write().unwrap();
+
+    let account = accounts.take(id_account).ok_or(ActixError::NotFound)?;
+    accounts.deleted.insert(id_account, account);
+
+    Ok(())
+  }
+
+  fn restore(&self, id_account: u64) -> Result<Account, ActixError> {
+    let mut accounts = self.accounts.write().unwrap();
+
+    let account = accounts.deleted.get(&id_account).ok_or(ActixError::NotFound)?;
+    if accounts.is_taken(&account.username, id_account) {
+      return Err(ActixError::SameAccountName);
+    }
+
+    let account = accounts.deleted.remove(&id_account).unwrap();
+    accounts.put(account.clone());
+
+    Ok(account)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn created_accounts_get_increasing_ids_and_unique_usernames() {
+    let store = MemoryAccountStore::new();
+    let alice = store.create("alice", "hash").unwrap();
+    let bob = store.create("bob", "hash").unwrap();
+
+    assert!(bob.id > alice.id);
+    assert!(matches!(store.create("alice", "other"), Err(ActixError::SameAccountName)));
+    assert_eq!(store.get_by_username("bob").unwrap().id, bob.id);
+  }
+
+  #[test]
+  fn deleted_accounts_are_hidden_until_restored() {
+    let store = MemoryAccountStore::new();
+    let alice = store.create("alice", "hash").unwrap();
+
+    store.delete(alice.id).unwrap();
+    assert!(matches!(store.get(alice.id), Err(ActixError::NotFound)));
+    assert!(store.list(0, 10).unwrap().is_empty());
+
+    assert_eq!(store.restore(alice.id).unwrap().username, "alice");
+    assert_eq!(store.get(alice.id).unwrap().username, "alice");
+  }
+
+  #[test]
+  fn restoring_is_refused_when_the_username_was_taken_again() {
+    let store = MemoryAccountStore::new();
+    let alice = store.create("alice", "hash").unwrap();
+    store.delete(alice.id).unwrap();
+    store.create("alice", "hash").unwrap();
+
+    assert!(matches!(store.restore(alice.id), Err(ActixError::SameAccountName)));
+  }
+
+  #[test]
+  fn renaming_to_a_taken_username_is_refused() {
+    let store = MemoryAccountStore::new();
+    let alice = store.create("alice", "hash").unwrap();
+    store.create("bob", "hash").unwrap();
+
+    assert!(matches!(store.update_username(alice.id, "bob"), Err(ActixError::SameAccountName)));
+    assert_eq!(store.get(alice.id).unwrap().username, "alice");
+
+    store.update_username(alice.id, "alice").unwrap();
+  }
+
+  #[test]
+  fn renaming_frees_the_previous_username() {
+    let store = MemoryAccountStore::new();
+    let alice = store.create("alice", "hash").unwrap();
+
+    store.update_username(alice.id, "alicia").unwrap();
+
+    assert!(matches!(store.get_by_username("alice"), Err(ActixError::NotFound)));
+    assert_eq!(store.get_by_username("alicia").unwrap().id, alice.id);
+    assert!(store.create("alice", "hash").is_ok());
+  }
+
+  #[test]
+  fn updates_only_touch_their_own_column() {
+    let store = MemoryAccountStore::new();
+    let alice = store.create("alice", "hash").unwrap();
+
+    store.update_username(alice.id, "alicia").unwrap();
+    let updated = store.update_password(alice.id, "rehashed").unwrap();
+    assert_eq!((updated.username.as_str(), updated.password.as_str()), ("alicia", "rehashed"));
+
+    store.delete(alice.id).unwrap();
+    assert!(matches!(store.update_password(alice.id, "hash"), Err(ActixError::NotFound)));
+    assert!(matches!(store.update_username(alice.id, "alice"), Err(ActixError::NotFound)));
+  }
+
+  #[test]
+  fn listings_see_writes_made_after_a_previous_listing() {
+    let store = MemoryAccountStore::new();
+    let alice = store.create("alice", "hash").unwrap();
+    assert_eq!(store.list(0, 10).unwrap().len(), 1);
+
+    store.create("bob", "hash").unwrap();
+    store.delete(alice.id).unwrap();
+
+    let usernames: Vec<String> = store.list(0, 10).unwrap().into_iter().map(|account| account.username).collect();
+    assert_eq!(usernames, vec![String::from("bob")]);
+  }
+
+  #[test]
+  fn list_pages_by_id() {
+    let store = MemoryAccountStore::new();
+    store.insert(Account { id: 3, username: String::from("c"), password: String::new() });
+    store.insert(Account { id: 1, username: String::from("a"), password: String::new() });
+    store.insert(Account { id: 2, username: String::from("b"), password: String::new() });
+
+    let page: Vec<u64> = store.list(1, 5).unwrap().iter().map(|account| account.id).collect();
+    assert_eq!(page, vec![2, 3]);
+    assert!(store.list(5, 5).unwrap().is_empty());
+    assert_eq!(store.create("d", "hash").unwrap().id, 4);
+  }
+}
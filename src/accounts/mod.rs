@@ -0,0 +1,95 @@
+use std::sync::Arc;
+use crate::{models::Account, errors::ActixError};
+
+mod bounded;
+mod cached;
+mod events;
+mod memory;
+
+pub use bounded::BoundedAccountCache;
+pub use cached::{CacheMode, CacheSettings, CacheStats, CachedAccountStore};
+pub use events::{AccountEvents, RedisAccountEvents};
+pub use memory::MemoryAccountStore;
+
+// Everything handlers need to read and write accounts. Deleted accounts are soft-deleted and
+// invisible to every method but `restore`. Implementations may block.
+pub trait AccountStore: Send + Sync {
+  fn create(&self, username: &str, hash_password: &str) -> Result<Account, ActixError>;
+  fn get(&self, id_account: u64) -> Result<Account, ActixError>;
+  fn get_by_username(&self, username: &str) -> Result<Account, ActixError>;
+  // Accounts ordered by id.
+  fn list(&self, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError>;
+  // Each writes a single column and returns the account as stored, so that a rename and a
+  // password change made at the same time do not undo one another.
+  fn update_username(&self, id_account: u64, username: &str) -> Result<Account, ActixError>;
+  fn update_password(&self, id_account: u64, hash_password: &str) -> Result<Account, ActixError>;
+  fn delete(&self, id_account: u64) -> Result<(), ActixError>;
+  fn restore(&self, id_account: u64) -> Result<Account, ActixError>;
+  // Reads the account again after it was written without going through the store. Only stores
+  // keeping accounts in memory have something to do.
+  fn refresh(&self, _id_account: u64) -> Result<(), ActixError> {
+    Ok(())
+  }
+}
+
+pub enum AccountChange {
+  Saved(Account),
+  Deleted(u64),
+}
+
+// Lets a cache catch up with the writes of other instances.
+pub trait AccountChangeFeed {
+  // Current time of the feed, in seconds since epoch.
+  fn now(&self) -> Result<i64, ActixError>;
+  // Changes made at or after `since`, and the feed time they were read at.
+  fn changes_since(&self, since: i64) -> Result<(Vec<AccountChange>, i64), ActixError>;
+}
+
+// Lets a cache own a store shared with the rest of the service.
+impl<T: AccountStore + ?Sized> AccountStore for Arc<T> {
+  fn create(&self, username: &str, hash_password: &str) -> Result<Account, ActixError> {
+    (**self).create(username, hash_password)
+  }
+
+  fn get(&self, id_account: u64) -> Result<Account, ActixError> {
+    (**self).get(id_account)
+  }
+
+  fn get_by_username(&self, username: &str) -> Result<Account, ActixError> {
+    (**self).get_by_username(username)
+  }
+
+  fn list(&self, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError> {
+    (**self).list(offset, limit)
+  }
+
+  fn update_username(&self, id_account: u64, username: &str) -> Result<Account, ActixError> {
+    (**self).update_username(id_account, username)
+  }
+
+  fn update_password(&self, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+    (**self).update_password(id_account, hash_password)
+  }
+
+  fn delete(&self, id_account: u64) -> Result<(), ActixError> {
+    (**self).delete(id_account)
+  }
+
+  fn restore(&self, id_account: u64) -> Result<Account, ActixError> {
+    (**self).restore(id_account)
+  }
+
+  fn refresh(&self, id_account: u64) -> Result<(), ActixError> {
+    (**self).refresh(id_account)
+  }
+}
+
+impl<T: AccountChangeFeed + ?Sized> AccountChangeFeed for Arc<T> {
+  fn now(&self) -> Result<i64, ActixError> {
+    (**self).now()
+  }
+
+  fn changes_since(&self, since: i64) -> Result<(Vec<AccountChange>, i64), ActixError> {
+    (**self).changes_since(since)
+  }
+}
@@ -0,0 +1,43 @@
+use std::future::{ready, Ready};
+use actix_web::{dev::Payload, FromRequest, HttpMessage, HttpRequest};
+use crate::errors::ActixError;
+use crate::models::Claims;
+
+// Lets an account act on every other account, as admins do.
+pub const MANAGE_ACCOUNTS: &str = "accounts:manage";
+
+// Inserted into the request extensions by `JwtCheck` once the bearer token has been validated.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+  pub claims: Claims,
+  pub token: String,
+}
+
+impl AuthenticatedUser {
+  pub fn has_permission(&self, permission: &str) -> bool {
+    self.claims.permissions.iter().any(|granted| granted == permission)
+  }
+
+  // Without `MANAGE_ACCOUNTS`, accounts may only act on themselves.
+  pub fn require_account_access(&self, id_account: u64) -> Result<(), ActixError> {
+    if self.has_permission(MANAGE_ACCOUNTS) || self.claims.id_account == id_account {
+      Ok(())
+    } else {
+      Err(ActixError::Forbidden)
+    }
+  }
+}
+
+impl FromRequest for AuthenticatedUser {
+  type Error = ActixError;
+  type Future = Ready<Result<Self, Self::Error>>;
+
+  fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+    ready(
+      req.extensions()
+        .get::<AuthenticatedUser>()
+        .cloned()
+        .ok_or(ActixError::UnAuthorized),
+    )
+  }
+}
@@ -0,0 +1,125 @@
+use std::env;
+use std::future::{ready, Ready};
+use std::net::{IpAddr, SocketAddr};
+use actix_web::{dev::Payload, http::header::HeaderMap, web, FromRequest, HttpRequest};
+use crate::errors::ActixError;
+
+// Reverse proxies whose `X-Forwarded-For` header is trusted to carry the real client address.
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies(Vec<IpAddr>);
+
+impl TrustedProxies {
+  pub fn new(proxies: Vec<IpAddr>) -> Self {
+    TrustedProxies(proxies)
+  }
+
+  // `TRUSTED_PROXIES` is a comma separated list of IP addresses.
+  pub fn from_env() -> Result<Self, String> {
+    match env::var("TRUSTED_PROXIES") {
+      Ok(proxies) => TrustedProxies::parse(&proxies),
+      Err(_) => Ok(TrustedProxies::default()),
+    }
+  }
+
+  fn parse(proxies: &str) -> Result<Self, String> {
+    proxies.split(',')
+      .map(str::trim)
+      .filter(|proxy| !proxy.is_empty())
+      .map(|proxy| proxy.parse().map_err(|_| format!("adresse invalide dans TRUSTED_PROXIES: {}", proxy)))
+      .collect::<Result<_, _>>()
+      .map(TrustedProxies::new)
+  }
+
+  pub fn client_ip(&self, peer_addr: Option<SocketAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+    let peer = peer_addr?.ip();
+    if !self.0.contains(&peer) {
+      return Some(peer);
+    }
+
+    let forwarded: Vec<&str> = headers.get_all("X-Forwarded-For")
+      .filter_map(|value| value.to_str().ok())
+      .flat_map(|value| value.split(','))
+      .collect();
+
+    // Walk back from the closest hop: the first address that is not one of our proxies is the
+    // client. Hops left of an unreadable one can't be told apart from what the client sent, so
+    // the walk stops there, at the last proxy seen.
+    let mut closest = peer;
+    for hop in forwarded.iter().rev() {
+      match hop.trim().parse() {
+        Ok(hop) if self.0.contains(&hop) => closest = hop,
+        Ok(hop) => return Some(hop),
+        Err(_) => break,
+      }
+    }
+
+    Some(closest)
+  }
+}
+
+// Address of the client as resolved by the `TrustedProxies` of the app, empty when unknown.
+pub struct ClientIp(pub String);
+
+impl FromRequest for ClientIp {
+  type Error = ActixError;
+  type Future = Ready<Result<Self, Self::Error>>;
+
+  fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+    let ip = match req.app_data::<web::Data<TrustedProxies>>() {
+      Some(proxies) => proxies.client_ip(req.peer_addr(), req.headers()),
+      None => TrustedProxies::default().client_ip(req.peer_addr(), req.headers()),
+    };
+
+    ready(Ok(ClientIp(ip.map(|ip| ip.to_string()).unwrap_or_default())))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use actix_web::http::header::{HeaderName, HeaderValue};
+
+  fn proxies() -> TrustedProxies {
+    TrustedProxies::parse("10.0.0.1, 10.0.0.2").unwrap()
+  }
+
+  fn client_ip(proxies: &TrustedProxies, peer: &str, forwarded: &[&str]) -> Option<IpAddr> {
+    let mut headers = HeaderMap::new();
+    for value in forwarded {
+      headers.append(HeaderName::from_static("x-forwarded-for"), HeaderValue::from_str(value).unwrap());
+    }
+
+    proxies.client_ip(Some(SocketAddr::new(peer.parse().unwrap(), 4000)), &headers)
+  }
+
+  fn ip(ip: &str) -> Option<IpAddr> {
+    Some(ip.parse().unwrap())
+  }
+
+  #[test]
+  fn the_header_is_ignored_from_untrusted_peers() {
+    assert_eq!(client_ip(&proxies(), "203.0.113.7", &["198.51.100.1"]), ip("203.0.113.7"));
+    assert_eq!(client_ip(&TrustedProxies::default(), "10.0.0.1", &["198.51.100.1"]), ip("10.0.0.1"));
+  }
+
+  #[test]
+  fn spoofed_leftmost_entries_are_ignored() {
+    assert_eq!(client_ip(&proxies(), "10.0.0.1", &["1.1.1.1, 203.0.113.7"]), ip("203.0.113.7"));
+    assert_eq!(client_ip(&proxies(), "10.0.0.1", &["1.1.1.1, 203.0.113.7, 10.0.0.2"]), ip("203.0.113.7"));
+    assert_eq!(client_ip(&proxies(), "10.0.0.1", &["1.1.1.1", "203.0.113.7"]), ip("203.0.113.7"));
+  }
+
+  #[test]
+  fn malformed_entries_stop_the_walk() {
+    assert_eq!(client_ip(&proxies(), "10.0.0.1", &["1.1.1.1, unknown"]), ip("10.0.0.1"));
+    assert_eq!(client_ip(&proxies(), "10.0.0.1", &["1.1.1.1, 203.0.113.7:4000, 10.0.0.2"]), ip("10.0.0.2"));
+    assert_eq!(client_ip(&proxies(), "10.0.0.1", &["garbage, 203.0.113.7"]), ip("203.0.113.7"));
+    assert_eq!(client_ip(&proxies(), "10.0.0.1", &[]), ip("10.0.0.1"));
+  }
+
+  #[test]
+  fn invalid_proxy_addresses_are_reported() {
+    assert!(TrustedProxies::parse("10.0.0.1, proxy.local").is_err());
+    assert_eq!(TrustedProxies::parse(" , ").unwrap().0, Vec::<IpAddr>::new());
+  }
+}
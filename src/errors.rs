@@ -6,10 +6,18 @@ use serde_json::to_string;
 #[derive(Debug, Display, Error, From)]
 pub enum ActixError {
   EmptyAccountName,
+  EmptyPassword,
   SameAccountName,
+  TwoFactorAlreadyEnabled,
   NotFound,
   UnAuthorized,
+  Forbidden,
+  TooManyAttempts,
+  TooManyRequests,
   MysqlError(mysql::Error),
+  SqliteError(rusqlite::Error),
+  #[cfg(feature = "postgres")]
+  PostgresError(postgres::Error),
   BlockingError(BlockingError),
   Unknown,
 }
@@ -17,24 +25,35 @@ pub enum ActixError {
 impl actix_web::error::ResponseError  for ActixError {
   fn status_code(&self) -> StatusCode {
       match self {
-        ActixError::EmptyAccountName | ActixError::NotFound => StatusCode::BAD_REQUEST,
-        ActixError::SameAccountName => StatusCode::CONFLICT,
+        ActixError::EmptyAccountName | ActixError::EmptyPassword | ActixError::NotFound => StatusCode::BAD_REQUEST,
+        ActixError::SameAccountName | ActixError::TwoFactorAlreadyEnabled => StatusCode::CONFLICT,
         ActixError::UnAuthorized => StatusCode::UNAUTHORIZED,
-        ActixError::MysqlError(_) | ActixError::Unknown | ActixError::BlockingError(_) => {
+        ActixError::Forbidden => StatusCode::FORBIDDEN,
+        ActixError::TooManyAttempts | ActixError::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+        ActixError::MysqlError(_) | ActixError::SqliteError(_) | ActixError::Unknown | ActixError::BlockingError(_) => {
           StatusCode::INTERNAL_SERVER_ERROR
         }
+        #[cfg(feature = "postgres")]
+        ActixError::PostgresError(_) => StatusCode::INTERNAL_SERVER_ERROR,
       }
   }
   fn error_response(&self) -> actix_web::HttpResponse<actix_web::body::BoxBody> {
     let status_code = self.status_code();
     let error_message = match self {
       ActixError::EmptyAccountName => "Account name cannot be empty.",
+      ActixError::EmptyPassword => "Password cannot be empty.",
       ActixError::SameAccountName => "Account already exist.",
+      ActixError::TwoFactorAlreadyEnabled => "Two-factor authentication already enabled.",
       ActixError::UnAuthorized => "Not authorize.",
+      ActixError::Forbidden => "Forbidden.",
+      ActixError::TooManyAttempts => "Too many failed attempts.",
+      ActixError::TooManyRequests => "Too many requests.",
       ActixError::NotFound => "Not found.",
-      ActixError::MysqlError(_) | ActixError::BlockingError(_) | ActixError::Unknown => {
+      ActixError::MysqlError(_) | ActixError::SqliteError(_) | ActixError::BlockingError(_) | ActixError::Unknown => {
         "Database error occurred."
       }
+      #[cfg(feature = "postgres")]
+      ActixError::PostgresError(_) => "Database error occurred.",
     };
 
     let error_response = BasicResponseError {
@@ -1,53 +1,152 @@
-use actix_web::{get, post, put, web, Responder, Result};
-use crate::accounts::AccountDB;
-use crate::persistance::accounts::{create_new_account, update_account_data};
+use actix_web::{delete, get, post, put, web, Responder, Result};
+use crate::accounts::AccountStore;
+use crate::auth::AuthenticatedUser;
 use crate::errors::ActixError;
-use crate::models::{Account, AccountData, QueryPageOptions, UpdateAccountData};
+use crate::lockout::LoginGuard;
+use crate::persistance::Database;
+use crate::password::Credentials;
+use crate::require_permission::RequirePermission;
+use crate::revocation::RevocationList;
+use crate::models::{AccountData, BasicResponse, QueryPageOptions, UpdateAccountData};
 
 
-#[get("/accounts")]
-async fn accounts_list_handler(opts: web::Query<QueryPageOptions>, accounts: web::Data<AccountDB>) -> impl Responder {
-  let accounts_lock  = accounts.get_accounts();
-
+#[get("/accounts", wrap = "RequirePermission(\"accounts:manage\")")]
+async fn accounts_list_handler(opts: web::Query<QueryPageOptions>, accounts: web::Data<dyn AccountStore>) -> Result<impl Responder, ActixError> {
   let limit = opts.limit.unwrap_or(10);
   let offset = (opts.page.unwrap_or(1) - 1) * limit;
 
-  let results: Vec<Account> = accounts_lock.iter().skip(offset).take(limit) .cloned().collect();
+  let results = web::block(move || accounts.list(offset, limit)).await??;
 
-  web::Json(results)
+  Ok(web::Json(results))
 }
 
 #[post("/accounts")]
-async fn accounts_create_handler(account_data: web::Json<AccountData>, db: web::Data<mysql::Pool>, accounts: web::Data<AccountDB>) -> Result<impl Responder, ActixError>{
-  if accounts.is_exist(&account_data.username) {
-    return Err(ActixError::SameAccountName);
+async fn accounts_create_handler(account_data: web::Json<AccountData>, accounts: web::Data<dyn AccountStore>, credentials: web::Data<Credentials>) -> Result<impl Responder, ActixError>{
+  if account_data.username.replace(' ', "").trim().is_empty() {
+    return Err(ActixError::EmptyAccountName);
   }
 
-  let hash_password = accounts.hash_password(&account_data.username, &account_data.password);
+  let account = web::block(move || {
+    if accounts.get_by_username(&account_data.username).is_ok() {
+      return Err(ActixError::SameAccountName);
+    }
+
+    let hash_password = credentials.hash(&account_data.username, &account_data.password)?;
+    accounts.create(&account_data.username, &hash_password)
+  }).await??;
+
+  Ok(web::Json(account))
+}
 
-  let account = web::block(move || create_new_account(&db, account_data.0, &hash_password)).await??;
+#[get("/accounts/{id_account}", wrap = "RequirePermission(\"accounts:read\")")]
+async fn account_get_handler(user: AuthenticatedUser, path: web::Path<u64>, accounts: web::Data<dyn AccountStore>) -> Result<impl Responder, ActixError>{
+  let id_account = path.into_inner();
+  user.require_account_access(id_account)?;
 
-  accounts.add_account(account.clone())?;
+  let account = web::block(move || accounts.get(id_account)).await??;
 
   Ok(web::Json(account))
 }
 
-#[get("/accounts/{id_account}")]
-async fn account_get_handler(path: web::Path<u64>, accounts: web::Data<AccountDB>) -> Result<impl Responder, ActixError>{
+#[put("/accounts/{id_account}", wrap = "RequirePermission(\"accounts:write\")")]
+async fn account_update_handler(user: AuthenticatedUser, path: web::Path<u64>, update: web::Json<UpdateAccountData>, accounts: web::Data<dyn AccountStore>) -> Result<impl Responder, ActixError>{
   let id_account = path.into_inner();
+  user.require_account_access(id_account)?;
 
-  let account = accounts.get_account(id_account)?;
+  let account = web::block(move || accounts.update_username(id_account, &update.username)).await??;
 
   Ok(web::Json(account))
 }
 
-#[put("/accounts/{id_account}")]
-async fn account_update_handler(path: web::Path<u64>, update: web::Json<UpdateAccountData>, db: web::Data<mysql::Pool>, accounts: web::Data<AccountDB>) -> Result<impl Responder, ActixError>{
+#[delete("/accounts/{id_account}", wrap = "RequirePermission(\"accounts:delete\")")]
+async fn account_delete_handler(user: AuthenticatedUser, path: web::Path<u64>, db: web::Data<dyn Database>, accounts: web::Data<dyn AccountStore>, revocations: web::Data<RevocationList>) -> Result<impl Responder, ActixError>{
+  let id_account = path.into_inner();
+  user.require_account_access(id_account)?;
+
+  // Tokens are revoked first: should the deletion then fail, the account only has to log in again.
+  let revoked_tokens = web::block(move || db.revoke_account_tokens(id_account)).await??;
+  revocations.extend(revoked_tokens);
+
+  web::block(move || accounts.delete(id_account)).await??;
+
+  Ok(web::Json(BasicResponse {
+    data: String::from("Account deleted."),
+  }))
+}
+
+#[post("/accounts/{id_account}/restore", wrap = "RequirePermission(\"accounts:manage\")")]
+async fn account_restore_handler(path: web::Path<u64>, accounts: web::Data<dyn AccountStore>) -> Result<impl Responder, ActixError>{
   let id_account = path.into_inner();
 
-  let account = accounts.update_username(id_account, &update.username)?;
-  web::block(move || update_account_data(&db, id_account, &update.username)).await??;
-  
+  let account = web::block(move || accounts.restore(id_account)).await??;
+
   Ok(web::Json(account))
 }
 
+#[post("/accounts/{id_account}/unlock", wrap = "RequirePermission(\"accounts:manage\")")]
+async fn account_unlock_handler(path: web::Path<u64>, db: web::Data<dyn Database>, accounts: web::Data<dyn AccountStore>, guard: web::Data<LoginGuard>) -> Result<impl Responder, ActixError>{
+  let id_account = path.into_inner();
+
+  web::block(move || {
+    let account = accounts.get(id_account)?;
+    guard.unlock(&**db, &account.username)
+  }).await??;
+
+  Ok(web::Json(BasicResponse {
+    data: String::from("Account unlocked."),
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+  use actix_web::{http::StatusCode, test, App};
+  use crate::accounts::MemoryAccountStore;
+  use crate::handlers::testing::{bearer, post, response, TestApp};
+  use crate::password::Md5Hasher;
+
+  #[actix_web::test]
+  async fn create_rejects_empty_and_duplicate_usernames() {
+    let accounts: Arc<dyn AccountStore> = Arc::new(MemoryAccountStore::new());
+    let app = test::init_service(
+      App::new()
+        .app_data(web::Data::from(accounts.clone()))
+        .app_data(web::Data::new(Credentials::with_hasher(Arc::new(Md5Hasher))))
+        .service(accounts_create_handler),
+    ).await;
+
+    let create = |username: &str| test::TestRequest::post()
+      .uri("/accounts")
+      .set_json(serde_json::json!({ "username": username, "password": "secret" }))
+      .to_request();
+
+    assert_eq!(test::call_service(&app, create("alice")).await.status(), StatusCode::OK);
+    assert_eq!(test::call_service(&app, create("alice")).await.status(), StatusCode::CONFLICT);
+    assert_eq!(test::call_service(&app, create("  ")).await.status(), StatusCode::BAD_REQUEST);
+
+    let alice = accounts.get_by_username("alice").unwrap();
+    assert_eq!(alice.password, format!("{:x}", md5::compute("alice:secret")));
+  }
+
+  #[actix_web::test]
+  async fn other_accounts_need_accounts_manage() {
+    let fixture = TestApp::new();
+    fixture.create_account("alice", "secret");
+    let bob = fixture.create_account("bob", "secret");
+    let app = test::init_service(fixture.app()).await;
+    let get = |uri: &str, token: &str| test::TestRequest::get().uri(uri).insert_header(bearer(token)).to_request();
+
+    let (_, login) = response(test::call_service(&app, post("/login", serde_json::json!({ "username": "alice", "password": "secret" })).to_request()).await).await;
+    let alice = login["access_token"].as_str().unwrap();
+    // Refused by `require_account_access`, then by `RequirePermission`.
+    assert_eq!(test::call_service(&app, get(&format!("/v1/accounts/{}", bob.id), alice)).await.status(), StatusCode::FORBIDDEN);
+    assert_eq!(test::call_service(&app, get("/v1/accounts", alice)).await.status(), StatusCode::FORBIDDEN);
+
+    let admin = fixture.access_token(0, &["accounts:read", "accounts:manage"]);
+    let (status, account) = response(test::call_service(&app, get(&format!("/v1/accounts/{}", bob.id), &admin)).await).await;
+    assert_eq!((status, account["username"].as_str()), (StatusCode::OK, Some("bob")));
+    let (status, accounts) = response(test::call_service(&app, get("/v1/accounts", &admin)).await).await;
+    assert_eq!((status, accounts.as_array().map(Vec::len)), (StatusCode::OK, Some(2)));
+  }
+}
@@ -1,21 +1,145 @@
-use actix_web::{post, web, Responder, Result};
-use crate::accounts::AccountDB;
+use actix_web::{post, web, Either, Responder, Result};
+use crate::accounts::AccountStore;
 use crate::errors::ActixError;
-use crate::persistance::access::create_new_access_token;
-use crate::models::LoginData;
+use crate::client_ip::ClientIp;
+use crate::lockout::LoginGuard;
+use crate::persistance::Database;
+use crate::password::{Credentials, PasswordCheck};
+use crate::models::{LoginData, LoginTwoFactorData, TwoFactorChallenge};
+use crate::tokens::{self, TokenKeys};
 
 
 #[post("/login")]
-async fn login_handler(login_data: web::Json<LoginData>, db: web::Data<mysql::Pool>, accounts: web::Data<AccountDB>) -> Result<impl Responder, ActixError> {
-  if !accounts.is_exist(&login_data.username) {
-    return Err(ActixError::SameAccountName);
+async fn login_handler(ClientIp(ip): ClientIp, login_data: web::Json<LoginData>, db: web::Data<dyn Database>, accounts: web::Data<dyn AccountStore>, credentials: web::Data<Credentials>, guard: web::Data<LoginGuard>, keys: web::Data<TokenKeys>) -> Result<impl Responder, ActixError> {
+  guard.check(&login_data.username, &ip)?;
+
+  let login = web::block(move || {
+    // Unknown usernames and wrong passwords must be indistinguishable, in status and in timing.
+    let account = accounts.get_by_username(&login_data.username).ok();
+    let check = credentials.verify(&login_data.username, &login_data.password, account.as_ref().map(|account| account.password.as_str()));
+    let account = match account {
+      Some(account) if check != PasswordCheck::Invalid => account,
+      _ => {
+        guard.record_failure(&**db, &login_data.username, &ip);
+        return Err(ActixError::UnAuthorized);
+      }
+    };
+
+    if check == PasswordCheck::Outdated {
+      // Transparently upgrade legacy hashes; a failure here must not block the login.
+      let rehashed = credentials.hash(&login_data.username, &login_data.password)
+        .and_then(|hash_password| accounts.update_password(account.id, &hash_password));
+      if let Err(err) = rehashed {
+        eprintln!("Erreur lors de la mise à jour du mot de passe: {}", err);
+      }
+    }
+
+    // Accounts with 2FA only get a challenge here, completed through `/login/2fa`. Their failures
+    // are only reset once the second factor is verified, or the password would reset every
+    // failed code.
+    match db.get_totp(account.id)? {
+      Some(account_totp) if account_totp.enabled => Ok(Either::Right(TwoFactorChallenge {
+        two_factor_required: true,
+        challenge_token: keys.create_two_factor_challenge(account.id)?,
+      })),
+      _ => {
+        guard.record_success(&**db, &account.username);
+        Ok(Either::Left(tokens::create_access_token(&**db, &keys, account.id)?))
+      },
+    }
+  }).await??;
+
+  Ok(match login {
+    Either::Left(access_token) => Either::Left(web::Json(access_token)),
+    Either::Right(challenge) => Either::Right(web::Json(challenge)),
+  })
+}
+
+#[post("/login/2fa")]
+async fn login_two_factor_handler(ClientIp(ip): ClientIp, login_data: web::Json<LoginTwoFactorData>, db: web::Data<dyn Database>, accounts: web::Data<dyn AccountStore>, guard: web::Data<LoginGuard>, keys: web::Data<TokenKeys>) -> Result<impl Responder, ActixError> {
+  let id_account = keys.decode_two_factor_challenge(&login_data.challenge_token)?;
+  let account = web::block(move || accounts.get(id_account)).await??;
+  guard.check(&account.username, &ip)?;
+
+  let access_token = web::block(move || {
+    let account_totp = db.get_totp(id_account)?
+      .filter(|account_totp| account_totp.enabled)
+      .ok_or(ActixError::UnAuthorized)?;
+    if let Err(err) = db.verify_second_factor(&account_totp, &login_data.code) {
+      guard.record_failure(&**db, &account.username, &ip);
+      return Err(err);
+    }
+    guard.record_success(&**db, &account.username);
+
+    tokens::create_access_token(&**db, &keys, id_account)
+  }).await??;
+
+  Ok(web::Json(access_token))
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::{http::StatusCode, test};
+  use serde_json::json;
+  use crate::accounts::AccountChangeFeed;
+  use crate::handlers::testing::{bearer, post, response, TestApp};
+  use crate::persistance::Database;
+  use crate::totp;
+
+  #[actix_web::test]
+  async fn unknown_usernames_and_wrong_passwords_get_the_same_answer() {
+    let fixture = TestApp::new();
+    fixture.create_account("alice", "secret");
+    let app = test::init_service(fixture.app()).await;
+
+    let unknown = response(test::call_service(&app, post("/login", json!({ "username": "bob", "password": "secret" })).to_request()).await).await;
+    let wrong = response(test::call_service(&app, post("/login", json!({ "username": "alice", "password": "wrong" })).to_request()).await).await;
+    assert_eq!(unknown.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(unknown, wrong);
   }
 
-  if !accounts.verify_credentials(&login_data.username, &login_data.password) {
-    return Err(ActixError::UnAuthorized);
+  #[actix_web::test]
+  async fn access_tokens_open_the_protected_routes() {
+    let fixture = TestApp::new();
+    let alice = fixture.create_account("alice", "secret");
+    let app = test::init_service(fixture.app()).await;
+
+    let (status, login) = response(test::call_service(&app, post("/login", json!({ "username": "alice", "password": "secret" })).to_request()).await).await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/v1/accounts/{}", alice.id);
+
+    let access_token = login["access_token"].as_str().unwrap();
+    let (status, account) = response(test::call_service(&app, test::TestRequest::get().uri(&uri).insert_header(bearer(access_token)).to_request()).await).await;
+    assert_eq!((status, account["username"].as_str()), (StatusCode::OK, Some("alice")));
+
+    assert_eq!(test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await.status(), StatusCode::UNAUTHORIZED);
+    let forged = fixture.access_token(alice.id, &["accounts:read"]).replace('.', "x");
+    assert_eq!(test::call_service(&app, test::TestRequest::get().uri(&uri).insert_header(bearer(&forged)).to_request()).await.status(), StatusCode::UNAUTHORIZED);
   }
 
-  let access_token = web::block(move || create_new_access_token(&db, accounts.get_account_by_username(&login_data.username)?.id)).await??;
+  #[actix_web::test]
+  async fn a_correct_password_does_not_reset_second_factor_failures() {
+    let fixture = TestApp::new();
+    let alice = fixture.create_account("alice", "secret");
+    let secret = totp::generate_secret();
+    fixture.db.save_pending_totp(alice.id, &secret).unwrap();
+    fixture.db.enable_totp(alice.id, &[]).unwrap();
+    let app = test::init_service(fixture.app()).await;
 
-  Ok(web::Json(access_token))
+    let password = || post("/login", json!({ "username": "alice", "password": "secret" })).to_request();
+    let code = |challenge: &serde_json::Value, code: &str| post("/login/2fa", json!({ "challenge_token": challenge["challenge_token"], "code": code })).to_request();
+
+    let (_, challenge) = response(test::call_service(&app, password()).await).await;
+    assert_eq!(challenge["two_factor_required"], json!(true));
+    for _ in 0..2 {
+      assert_eq!(test::call_service(&app, code(&challenge, "000000")).await.status(), StatusCode::UNAUTHORIZED);
+    }
+    let (status, challenge) = response(test::call_service(&app, password()).await).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(test::call_service(&app, code(&challenge, "000000")).await.status(), StatusCode::UNAUTHORIZED);
+
+    // The third failed code locked the account, even though the password was right in between.
+    let valid = totp::code_at(&secret, fixture.db.now().unwrap() as u64);
+    assert_eq!(test::call_service(&app, code(&challenge, &valid)).await.status(), StatusCode::TOO_MANY_REQUESTS);
+  }
 }
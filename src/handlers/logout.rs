@@ -0,0 +1,62 @@
+use actix_web::{post, web, Responder, Result};
+use crate::auth::AuthenticatedUser;
+use crate::errors::ActixError;
+use crate::persistance::Database;
+use crate::revocation::RevocationList;
+use crate::models::BasicResponse;
+
+
+#[post("/logout")]
+async fn logout_handler(user: AuthenticatedUser, db: web::Data<dyn Database>, revocations: web::Data<RevocationList>) -> Result<impl Responder, ActixError> {
+  let token = user.token.clone();
+  web::block(move || db.revoke_access_token(&token)).await??;
+  revocations.revoke(&user.token, user.claims.exp);
+
+  Ok(web::Json(BasicResponse {
+    data: String::from("Token revoked."),
+  }))
+}
+
+#[post("/logout/all")]
+async fn logout_all_handler(user: AuthenticatedUser, db: web::Data<dyn Database>, revocations: web::Data<RevocationList>) -> Result<impl Responder, ActixError> {
+  let revoked_tokens = web::block(move || db.revoke_account_tokens(user.claims.id_account)).await??;
+  revocations.extend(revoked_tokens);
+
+  Ok(web::Json(BasicResponse {
+    data: String::from("All tokens revoked."),
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::{http::StatusCode, test};
+  use serde_json::json;
+  use crate::handlers::testing::{bearer, post, response, TestApp};
+
+  #[actix_web::test]
+  async fn revoked_tokens_are_refused() {
+    let fixture = TestApp::new();
+    let alice = fixture.create_account("alice", "secret");
+    let app = test::init_service(fixture.app()).await;
+    let login = || post("/login", json!({ "username": "alice", "password": "secret" })).to_request();
+    let get = |token: &serde_json::Value| test::TestRequest::get()
+      .uri(&format!("/v1/accounts/{}", alice.id))
+      .insert_header(bearer(token["access_token"].as_str().unwrap()))
+      .to_request();
+    let logout = |uri: &str, token: &serde_json::Value| test::TestRequest::post()
+      .uri(uri)
+      .insert_header(bearer(token["access_token"].as_str().unwrap()))
+      .to_request();
+
+    let (_, first) = response(test::call_service(&app, login()).await).await;
+    let (_, second) = response(test::call_service(&app, login()).await).await;
+    assert_eq!(test::call_service(&app, logout("/v1/logout", &first)).await.status(), StatusCode::OK);
+    assert_eq!(test::call_service(&app, get(&first)).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, get(&second)).await.status(), StatusCode::OK);
+
+    let (_, third) = response(test::call_service(&app, login()).await).await;
+    assert_eq!(test::call_service(&app, logout("/v1/logout/all", &third)).await.status(), StatusCode::OK);
+    assert_eq!(test::call_service(&app, get(&second)).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, get(&third)).await.status(), StatusCode::UNAUTHORIZED);
+  }
+}
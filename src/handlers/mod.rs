@@ -1,3 +1,44 @@
+use actix_web::web;
+use crate::jwt_check::JwtCheck;
+
 pub mod healthcheck;
 pub mod accounts;
-pub mod login;
\ No newline at end of file
+pub mod login;
+pub mod token;
+pub mod logout;
+pub mod password;
+pub mod two_factor;
+#[cfg(test)]
+mod testing;
+
+// Every route of the API. Those under `/v1` need an access token, checked by `jwt_check`, but for
+// account creation and token refresh.
+pub fn configure(cfg: &mut web::ServiceConfig, jwt_check: JwtCheck) {
+  cfg.service(healthcheck::health_checker_handler)
+    .service(
+      web::scope("/v1")
+        .service(accounts::accounts_create_handler)
+        .service(token::refresh_token_handler)
+        .service(
+          web::scope("")
+            .wrap(jwt_check)
+            .service(accounts::accounts_list_handler)
+            .service(accounts::account_get_handler)
+            .service(accounts::account_update_handler)
+            .service(accounts::account_delete_handler)
+            .service(accounts::account_restore_handler)
+            .service(accounts::account_unlock_handler)
+            .service(password::password_update_handler)
+            .service(password::password_reset_handler)
+            .service(two_factor::two_factor_enroll_handler)
+            .service(two_factor::two_factor_confirm_handler)
+            .service(two_factor::two_factor_disable_handler)
+            .service(logout::logout_handler)
+            .service(logout::logout_all_handler)
+        )
+    )
+    .service(login::login_handler)
+    .service(login::login_two_factor_handler)
+    .service(password::forgot_password_handler)
+    .service(password::reset_forgotten_password_handler);
+}
@@ -0,0 +1,199 @@
+use actix_web::{post, put, rt, web, Responder, Result};
+use crate::accounts::AccountStore;
+use crate::auth::AuthenticatedUser;
+use crate::client_ip::ClientIp;
+use crate::errors::ActixError;
+use crate::lockout::LoginGuard;
+use crate::mailer::Mailer;
+use crate::password::{hash_token, random_token, Credentials, PasswordCheck};
+use crate::persistance::Database;
+use crate::persistance::PASSWORD_RESET_LIFETIME_MINUTES;
+use crate::require_permission::RequirePermission;
+use crate::revocation::RevocationList;
+use crate::models::{BasicResponse, ForgotPasswordData, ResetForgottenPasswordData, ResetPasswordData, UpdatePasswordData};
+
+
+#[put("/accounts/{id_account}/password", wrap = "RequirePermission(\"accounts:write\")")]
+#[allow(clippy::too_many_arguments)]
+async fn password_update_handler(user: AuthenticatedUser, path: web::Path<u64>, ClientIp(ip): ClientIp, update: web::Json<UpdatePasswordData>, db: web::Data<dyn Database>, accounts: web::Data<dyn AccountStore>, credentials: web::Data<Credentials>, guard: web::Data<LoginGuard>, revocations: web::Data<RevocationList>) -> Result<impl Responder, ActixError> {
+  let id_account = path.into_inner();
+  if user.claims.id_account != id_account {
+    return Err(ActixError::Forbidden);
+  }
+
+  // The current password counts as a login attempt, so a stolen token can't be used to guess it.
+  let (verifier, check_db, check_credentials) = (accounts.clone(), db.clone(), credentials.clone());
+  let current_password = update.current_password.clone();
+  web::block(move || {
+    let account = verifier.get(id_account)?;
+    guard.check(&account.username, &ip)?;
+    if check_credentials.verify(&account.username, &current_password, Some(&account.password)) == PasswordCheck::Invalid {
+      guard.record_failure(&**check_db, &account.username, &ip);
+      return Err(ActixError::UnAuthorized);
+    }
+    guard.record_success(&**check_db, &account.username);
+    Ok(())
+  }).await??;
+
+  replace_password(id_account, update.into_inner().new_password, db, accounts, credentials, revocations).await?;
+
+  Ok(web::Json(BasicResponse {
+    data: String::from("Password updated."),
+  }))
+}
+
+#[put("/accounts/{id_account}/password/reset", wrap = "RequirePermission(\"accounts:manage\")")]
+async fn password_reset_handler(path: web::Path<u64>, reset: web::Json<ResetPasswordData>, db: web::Data<dyn Database>, accounts: web::Data<dyn AccountStore>, credentials: web::Data<Credentials>, revocations: web::Data<RevocationList>) -> Result<impl Responder, ActixError> {
+  let id_account = path.into_inner();
+
+  replace_password(id_account, reset.into_inner().new_password, db, accounts, credentials, revocations).await?;
+
+  Ok(web::Json(BasicResponse {
+    data: String::from("Password reset."),
+  }))
+}
+
+#[post("/password/forgot")]
+async fn forgot_password_handler(forgot: web::Json<ForgotPasswordData>, db: web::Data<dyn Database>, accounts: web::Data<dyn AccountStore>, mailer: web::Data<dyn Mailer>) -> impl Responder {
+  // The token is issued in the background so that neither the response nor its timing tells
+  // whether the username exists.
+  let mailer = mailer.into_inner();
+  rt::spawn(async move {
+    let sent = web::block(move || {
+      let Ok(account) = accounts.get_by_username(&forgot.username) else {
+        return Ok(());
+      };
+      let token = random_token(48);
+      db.create_password_reset(account.id, &hash_token(&token))?;
+      mailer.send(
+        &account.username,
+        "Password reset",
+        &format!("Use this token to reset your password: {}\nIt expires in {} minutes.", token, PASSWORD_RESET_LIFETIME_MINUTES),
+      )
+    }).await;
+
+    match sent {
+      Ok(Ok(())) => {}
+      Ok(Err(err)) => eprintln!("Erreur lors de l'envoi du jeton de réinitialisation: {}", err),
+      Err(err) => eprintln!("Erreur lors de l'envoi du jeton de réinitialisation: {}", err),
+    }
+  });
+
+  web::Json(BasicResponse {
+    data: String::from("If the account exists, a reset token has been sent."),
+  })
+}
+
+#[post("/password/reset")]
+async fn reset_forgotten_password_handler(reset: web::Json<ResetForgottenPasswordData>, db: web::Data<dyn Database>, accounts: web::Data<dyn AccountStore>, credentials: web::Data<Credentials>, revocations: web::Data<RevocationList>) -> Result<impl Responder, ActixError> {
+  let reset = reset.into_inner();
+  if reset.new_password.is_empty() {
+    return Err(ActixError::EmptyPassword);
+  }
+
+  let token_hash = hash_token(&reset.token);
+  let revoked_tokens = web::block(move || {
+    let id_account = db.get_password_reset(&token_hash)?;
+    let account = accounts.get(id_account)?;
+    let hash_password = credentials.hash(&account.username, &reset.new_password)?;
+    // The token is only used up once the new password is stored.
+    db.reset_password(&token_hash, &hash_password)?;
+    if let Err(err) = accounts.refresh(id_account) {
+      eprintln!("Erreur lors du rechargement du compte: {}", err);
+    }
+    db.revoke_account_tokens(id_account)
+  }).await??;
+
+  revocations.extend(revoked_tokens);
+
+  Ok(web::Json(BasicResponse {
+    data: String::from("Password reset."),
+  }))
+}
+
+// Stores the new hash and revokes every token of the account so existing sessions must log in again.
+async fn replace_password(id_account: u64, new_password: String, db: web::Data<dyn Database>, accounts: web::Data<dyn AccountStore>, credentials: web::Data<Credentials>, revocations: web::Data<RevocationList>) -> Result<(), ActixError> {
+  if new_password.is_empty() {
+    return Err(ActixError::EmptyPassword);
+  }
+
+  let revoked_tokens = web::block(move || {
+    let account = accounts.get(id_account)?;
+    let hash_password = credentials.hash(&account.username, &new_password)?;
+    accounts.update_password(id_account, &hash_password)?;
+    db.revoke_account_tokens(id_account)
+  }).await??;
+
+  revocations.extend(revoked_tokens);
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::{http::StatusCode, test};
+  use serde_json::{json, Value};
+  use crate::handlers::testing::{bearer, post, response, TestApp};
+
+  fn login(password: &str) -> test::TestRequest {
+    post("/login", json!({ "username": "alice", "password": password }))
+  }
+
+  fn change_password(id_account: u64, token: &Value, current_password: &str) -> test::TestRequest {
+    test::TestRequest::put()
+      .uri(&format!("/v1/accounts/{}/password", id_account))
+      .insert_header(bearer(token["access_token"].as_str().unwrap()))
+      .set_json(json!({ "current_password": current_password, "new_password": "changed" }))
+  }
+
+  #[actix_web::test]
+  async fn password_changes_need_the_current_password_and_end_sessions() {
+    let fixture = TestApp::new();
+    let alice = fixture.create_account("alice", "secret");
+    let app = test::init_service(fixture.app()).await;
+
+    let (_, token) = response(test::call_service(&app, login("secret").to_request()).await).await;
+    assert_eq!(test::call_service(&app, change_password(alice.id, &token, "wrong").to_request()).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, change_password(alice.id, &token, "secret").to_request()).await.status(), StatusCode::OK);
+
+    let get = test::TestRequest::get().uri(&format!("/v1/accounts/{}", alice.id)).insert_header(bearer(token["access_token"].as_str().unwrap()));
+    assert_eq!(test::call_service(&app, get.to_request()).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, login("secret").to_request()).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, login("changed").to_request()).await.status(), StatusCode::OK);
+  }
+
+  #[actix_web::test]
+  async fn wrong_current_passwords_count_as_failed_logins() {
+    let fixture = TestApp::new();
+    let alice = fixture.create_account("alice", "secret");
+    let app = test::init_service(fixture.app()).await;
+
+    let (_, token) = response(test::call_service(&app, login("secret").to_request()).await).await;
+    for _ in 0..3 {
+      assert_eq!(test::call_service(&app, change_password(alice.id, &token, "wrong").to_request()).await.status(), StatusCode::UNAUTHORIZED);
+    }
+    assert_eq!(test::call_service(&app, change_password(alice.id, &token, "secret").to_request()).await.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(test::call_service(&app, login("secret").to_request()).await.status(), StatusCode::TOO_MANY_REQUESTS);
+  }
+
+  #[actix_web::test]
+  async fn reset_tokens_work_once_and_expire() {
+    let fixture = TestApp::new();
+    fixture.create_account("alice", "secret");
+    let app = test::init_service(fixture.app()).await;
+    let forgot = || post("/password/forgot", json!({ "username": "alice" })).to_request();
+    let reset = |token: &str, new_password: &str| post("/password/reset", json!({ "token": token, "new_password": new_password })).to_request();
+
+    assert_eq!(test::call_service(&app, forgot()).await.status(), StatusCode::OK);
+    let token = fixture.sent_reset_token().await;
+    assert_eq!(test::call_service(&app, reset(&token, "changed")).await.status(), StatusCode::OK);
+    assert_eq!(test::call_service(&app, reset(&token, "again")).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, login("changed").to_request()).await.status(), StatusCode::OK);
+
+    test::call_service(&app, forgot()).await;
+    let token = fixture.sent_reset_token().await;
+    fixture.db.execute("UPDATE password_reset SET expires_at = datetime('now', '-1 minutes') WHERE used_at IS NULL");
+    assert_eq!(test::call_service(&app, reset(&token, "expired")).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, login("changed").to_request()).await.status(), StatusCode::OK);
+  }
+}
@@ -0,0 +1,114 @@
+// The whole API on an in-memory SQLite database, for the tests of the handlers.
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use actix_web::{
+  body::MessageBody,
+  dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+  http::{header::{HeaderName, AUTHORIZATION}, StatusCode},
+  rt, test, web, App, Error,
+};
+use serde_json::Value;
+use crate::accounts::AccountStore;
+use crate::jwt_check::JwtCheck;
+use crate::lockout::{LockoutPolicy, LoginGuard};
+use crate::mailer::{LogMailer, Mailer};
+use crate::migrations::{run, MigrateArgs, MigrateCommand};
+use crate::models::Account;
+use crate::password::{random_token, Credentials, Md5Hasher};
+use crate::persistance::{Database, SqliteDatabase};
+use crate::revocation::RevocationList;
+use crate::tokens::TokenKeys;
+
+pub struct TestApp {
+  pub db: Arc<SqliteDatabase>,
+  pub keys: TokenKeys,
+  revocations: RevocationList,
+  guard: LoginGuard,
+  credentials: Credentials,
+  mail_log: PathBuf,
+}
+
+impl TestApp {
+  // Accounts are locked out at their third failure, without delays before.
+  pub fn new() -> TestApp {
+    let db = SqliteDatabase::open(":memory:").unwrap();
+    run(&db, &MigrateArgs { command: MigrateCommand::Up, dry_run: false }).unwrap();
+
+    TestApp {
+      db: Arc::new(db),
+      keys: TokenKeys::new("token_secret", "refresh_token_secret"),
+      revocations: RevocationList::new(),
+      guard: LoginGuard::new(
+        LockoutPolicy { threshold: 3, lockout_seconds: 900, base_delay_seconds: 0 },
+        LockoutPolicy { threshold: 100, lockout_seconds: 900, base_delay_seconds: 0 },
+      ),
+      credentials: Credentials::with_hasher(Arc::new(Md5Hasher)),
+      mail_log: env::temp_dir().join(format!("nyu_model_mails_{}.log", random_token(8))),
+    }
+  }
+
+  pub fn app(&self) -> App<impl ServiceFactory<ServiceRequest, Config = (), Response = ServiceResponse, Error = Error, InitError = ()>> {
+    let db: Arc<dyn Database> = self.db.clone();
+    let accounts: Arc<dyn AccountStore> = self.db.clone();
+    let mailer: Arc<dyn Mailer> = Arc::new(LogMailer::new(Some(self.mail_log.clone())));
+    let jwt_check = JwtCheck::new(self.revocations.clone(), self.keys.clone());
+
+    App::new()
+      .app_data(web::Data::from(accounts))
+      .app_data(web::Data::new(self.credentials.clone()))
+      .app_data(web::Data::new(self.revocations.clone()))
+      .app_data(web::Data::new(self.guard.clone()))
+      .app_data(web::Data::from(db))
+      .app_data(web::Data::from(mailer))
+      .app_data(web::Data::new(self.keys.clone()))
+      .configure(|cfg| super::configure(cfg, jwt_check))
+  }
+
+  pub fn create_account(&self, username: &str, password: &str) -> Account {
+    self.db.create(username, &self.credentials.hash(username, password).unwrap()).unwrap()
+  }
+
+  // Signed like those of `/login`, but with any permissions.
+  pub fn access_token(&self, id_account: u64, permissions: &[&str]) -> String {
+    let permissions = permissions.iter().map(|permission| permission.to_string()).collect();
+    self.keys.encode_token_pair(id_account, Vec::new(), permissions).unwrap().0
+  }
+
+  // Waits for the next reset token written to the mail log: they are sent in the background.
+  pub async fn sent_reset_token(&self) -> String {
+    for _ in 0..100 {
+      let mails = fs::read_to_string(&self.mail_log).unwrap_or_default();
+      if let Some(token) = mails.lines().find_map(|line| line.strip_prefix("Use this token to reset your password: ")) {
+        fs::remove_file(&self.mail_log).unwrap();
+        return token.to_string();
+      }
+      rt::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("no reset token was sent");
+  }
+}
+
+impl Drop for TestApp {
+  fn drop(&mut self) {
+    let _ = fs::remove_file(&self.mail_log);
+  }
+}
+
+pub fn bearer(token: &str) -> (HeaderName, String) {
+  (AUTHORIZATION, format!("Bearer {}", token))
+}
+
+// Status and JSON body of a response, `Null` for an empty body.
+pub async fn response<B: MessageBody>(res: ServiceResponse<B>) -> (StatusCode, Value) {
+  let status = res.status();
+  let body = test::read_body(res).await;
+
+  (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+pub fn post(uri: &str, body: Value) -> test::TestRequest {
+  test::TestRequest::post().uri(uri).set_json(body)
+}
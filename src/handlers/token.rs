@@ -0,0 +1,37 @@
+use actix_web::{post, web, Responder, Result};
+use crate::errors::ActixError;
+use crate::persistance::Database;
+use crate::models::RefreshTokenData;
+use crate::tokens::{self, TokenKeys};
+
+
+#[post("/token/refresh")]
+async fn refresh_token_handler(refresh_data: web::Json<RefreshTokenData>, db: web::Data<dyn Database>, keys: web::Data<TokenKeys>) -> Result<impl Responder, ActixError> {
+  let access_token = web::block(move || tokens::refresh_access_token(&**db, &keys, &refresh_data.refresh_token)).await??;
+
+  Ok(web::Json(access_token))
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::{http::StatusCode, test};
+  use serde_json::json;
+  use crate::handlers::testing::{post, response, TestApp};
+
+  #[actix_web::test]
+  async fn refresh_tokens_are_rotated() {
+    let fixture = TestApp::new();
+    fixture.create_account("alice", "secret");
+    let app = test::init_service(fixture.app()).await;
+    let refresh = |token: &serde_json::Value| post("/v1/token/refresh", json!({ "refresh_token": token["refresh_token"] })).to_request();
+
+    let (_, login) = response(test::call_service(&app, post("/login", json!({ "username": "alice", "password": "secret" })).to_request()).await).await;
+    let (status, refreshed) = response(test::call_service(&app, refresh(&login)).await).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(refreshed["refresh_token"], login["refresh_token"]);
+
+    // Reusing the first refresh token revokes the one that replaced it too.
+    assert_eq!(test::call_service(&app, refresh(&login)).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, refresh(&refreshed)).await.status(), StatusCode::UNAUTHORIZED);
+  }
+}
@@ -0,0 +1,82 @@
+use actix_web::{post, web, Responder, Result};
+use std::env;
+use crate::accounts::AccountStore;
+use crate::auth::{AuthenticatedUser, MANAGE_ACCOUNTS};
+use crate::errors::ActixError;
+use crate::password::random_token;
+use crate::persistance::Database;
+use crate::require_permission::RequirePermission;
+use crate::totp;
+use crate::models::{BasicResponse, RecoveryCodes, TwoFactorCodeData, TwoFactorEnrollment};
+
+const RECOVERY_CODES_COUNT: usize = 10;
+
+
+#[post("/accounts/{id_account}/2fa/enroll", wrap = "RequirePermission(\"accounts:write\")")]
+async fn two_factor_enroll_handler(user: AuthenticatedUser, path: web::Path<u64>, db: web::Data<dyn Database>, accounts: web::Data<dyn AccountStore>) -> Result<impl Responder, ActixError> {
+  let id_account = path.into_inner();
+  if user.claims.id_account != id_account {
+    return Err(ActixError::Forbidden);
+  }
+
+  let secret = totp::generate_secret();
+  let pending_secret = secret.clone();
+  let account = web::block(move || {
+    let account = accounts.get(id_account)?;
+    if db.get_totp(id_account)?.map(|account_totp| account_totp.enabled).unwrap_or(false) {
+      return Err(ActixError::TwoFactorAlreadyEnabled);
+    }
+    db.save_pending_totp(id_account, &pending_secret)?;
+    Ok(account)
+  }).await??;
+
+  let issuer = env::var("TOTP_ISSUER").unwrap_or_else(|_| String::from("NyuModel"));
+  Ok(web::Json(TwoFactorEnrollment {
+    otpauth_uri: totp::otpauth_uri(&issuer, &account.username, &secret),
+    secret,
+  }))
+}
+
+#[post("/accounts/{id_account}/2fa/confirm", wrap = "RequirePermission(\"accounts:write\")")]
+async fn two_factor_confirm_handler(user: AuthenticatedUser, path: web::Path<u64>, confirm: web::Json<TwoFactorCodeData>, db: web::Data<dyn Database>) -> Result<impl Responder, ActixError> {
+  let id_account = path.into_inner();
+  if user.claims.id_account != id_account {
+    return Err(ActixError::Forbidden);
+  }
+  if !totp::is_totp_code(&confirm.code) {
+    return Err(ActixError::UnAuthorized);
+  }
+
+  let recovery_codes: Vec<String> = (0..RECOVERY_CODES_COUNT).map(|_| random_token(10)).collect();
+  let stored_codes = recovery_codes.clone();
+  web::block(move || {
+    let account_totp = db.get_totp(id_account)?.ok_or(ActixError::NotFound)?;
+    if account_totp.enabled {
+      return Err(ActixError::TwoFactorAlreadyEnabled);
+    }
+    db.verify_second_factor(&account_totp, &confirm.code)?;
+    db.enable_totp(id_account, &stored_codes)
+  }).await??;
+
+  Ok(web::Json(RecoveryCodes { recovery_codes }))
+}
+
+#[post("/accounts/{id_account}/2fa/disable", wrap = "RequirePermission(\"accounts:write\")")]
+async fn two_factor_disable_handler(user: AuthenticatedUser, path: web::Path<u64>, disable: web::Json<TwoFactorCodeData>, db: web::Data<dyn Database>) -> Result<impl Responder, ActixError> {
+  let id_account = path.into_inner();
+  user.require_account_access(id_account)?;
+
+  // Account managers can switch 2FA off for users who lost their device; everyone else proves possession.
+  let code_required = !user.has_permission(MANAGE_ACCOUNTS);
+  web::block(move || {
+    let account_totp = db.get_totp(id_account)?.ok_or(ActixError::NotFound)?;
+    if code_required && account_totp.enabled {
+      db.verify_second_factor(&account_totp, &disable.code)?;
+    }
+    db.disable_totp(id_account)
+  }).await??;
+
+  Ok(web::Json(BasicResponse {
+    data: String::from("Two-factor authentication disabled."),
+  }))
+}
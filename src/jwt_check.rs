@@ -3,14 +3,31 @@ use std::future::{ready, Ready};
 use actix_web::{
   body::EitherBody,
   dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-  Error, HttpResponse,
+  http::header::HeaderMap,
+  Error, HttpMessage, HttpResponse,
 };
 use futures_util::{future::LocalBoxFuture, FutureExt, TryFutureExt};
-use jsonwebtoken::{decode, DecodingKey, Algorithm, Validation};
-use std::env;
-use crate::models::Claims;
+use crate::auth::AuthenticatedUser;
+use crate::revocation::RevocationList;
+use crate::tokens::TokenKeys;
 
-pub struct JwtCheck;
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+  headers.get("Authorization")
+    .and_then(|header_value| header_value.to_str().ok())
+    .map(|token| token.trim_start_matches("Bearer "))
+}
+
+// Holds the revocation list and keys itself, so that a route can't be wrapped without them.
+pub struct JwtCheck {
+  revocations: RevocationList,
+  keys: TokenKeys,
+}
+
+impl JwtCheck {
+  pub fn new(revocations: RevocationList, keys: TokenKeys) -> Self {
+    JwtCheck { revocations, keys }
+  }
+}
 
 impl<S, B> Transform<S, ServiceRequest> for JwtCheck
 where
@@ -25,13 +42,15 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-      ready(Ok(JwtCheckMiddleware { service }))
+      ready(Ok(JwtCheckMiddleware { service, revocations: self.revocations.clone(), keys: self.keys.clone() }))
     }
 }
 
 
 pub struct JwtCheckMiddleware<S> {
   service: S,
+  revocations: RevocationList,
+  keys: TokenKeys,
 }
 
 impl<S, B> Service<ServiceRequest> for JwtCheckMiddleware<S>
@@ -47,42 +66,20 @@ where
   forward_ready!(service);
 
   fn call(&self, req: ServiceRequest) -> Self::Future {
-    let headers = req.headers();
-    let auth_header = headers.get("Authorization");
-    match auth_header {
-      Some(header_value) => {
-        if let Ok(token) = header_value.to_str() {
+    let user = bearer_token(req.headers()).and_then(|token| {
+      if self.revocations.is_revoked(token) {
+        return None;
+      }
 
-          let token_secret = match env::var("TOKEN_SECRET") {
-            Ok(secret) => secret,
-            Err(_) => return Box::pin(async {
-              Ok(req.into_response(
-                HttpResponse::Unauthorized()
-                  .finish()
-                  .map_into_right_body(),
-              ))
-            }),
-          };
+      self.keys.decode_access_token(token).ok().map(|claims| AuthenticatedUser {
+        claims,
+        token: token.to_string(),
+      })
+    });
 
-          let validation = Validation::new(Algorithm::HS256);
-          match decode::<Claims>(&token.replace("Bearer ", ""), &DecodingKey::from_secret(token_secret.as_ref()), &validation) {
-            Ok(_) => {
-              return self.service
-                .call(req)
-                .map_ok(ServiceResponse::map_into_left_body)
-                .boxed_local()
-            },
-            Err(_) => {
-              return Box::pin(async {
-                Ok(req.into_response(
-                  HttpResponse::Unauthorized()
-                    .finish()
-                    .map_into_right_body(),
-                ))
-              })
-            }
-          }
-        }
+    match user {
+      Some(user) => {
+        req.extensions_mut().insert(user);
       },
       None => {
         return Box::pin(async {
@@ -0,0 +1,18 @@
+pub mod handlers;
+pub mod models;
+pub mod persistance;
+pub mod errors;
+pub mod accounts;
+pub mod client_ip;
+pub mod auth;
+pub mod password;
+pub mod jwt_check;
+pub mod lockout;
+pub mod mailer;
+pub mod rate_limit;
+pub mod revocation;
+pub mod totp;
+pub mod purge;
+pub mod require_permission;
+pub mod migrations;
+pub mod tokens;
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use chrono::Utc;
+use crate::errors::ActixError;
+use crate::persistance::Database;
+use crate::persistance::LoginAttemptRow;
+
+#[derive(Clone, Debug)]
+pub struct LockoutPolicy {
+  // Failures tolerated (with growing delays) before the key is locked out.
+  pub threshold: u32,
+  pub lockout_seconds: i64,
+  pub base_delay_seconds: i64,
+}
+
+impl LockoutPolicy {
+  fn from_env(prefix: &str, threshold: u32) -> Result<Self, String> {
+    LockoutPolicy {
+      threshold: read_env(prefix, "LOCKOUT_THRESHOLD", threshold)?,
+      lockout_seconds: read_env(prefix, "LOCKOUT_SECONDS", 900)?,
+      base_delay_seconds: read_env(prefix, "LOGIN_DELAY_SECONDS", 1)?,
+    }.validate(prefix)
+  }
+
+  // A zero threshold would lock keys out at their first failure.
+  fn validate(self, prefix: &str) -> Result<Self, String> {
+    if self.threshold == 0 {
+      return Err(format!("{}LOCKOUT_THRESHOLD doit être supérieur à zéro", prefix));
+    }
+    Ok(self)
+  }
+
+  fn blocked_until(&self, failures: u32, now: i64) -> i64 {
+    if failures >= self.threshold {
+      now + self.lockout_seconds
+    } else {
+      let delay = self.base_delay_seconds.saturating_mul(1 << (failures - 1).min(30));
+      now + delay.min(self.lockout_seconds)
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Attempts {
+  failures: u32,
+  blocked_until: i64,
+  last_failure: i64,
+}
+
+// Tracks failed logins per account and per client IP. State is written through to the
+// `login_attempts` table so that lockouts survive restarts.
+#[derive(Clone)]
+pub struct LoginGuard {
+  account_policy: LockoutPolicy,
+  ip_policy: LockoutPolicy,
+  attempts: Arc<Mutex<HashMap<String, Attempts>>>,
+}
+
+impl LoginGuard {
+  pub fn new(account_policy: LockoutPolicy, ip_policy: LockoutPolicy) -> LoginGuard {
+    LoginGuard {
+      account_policy,
+      ip_policy,
+      attempts: Arc::new(Mutex::new(HashMap::new())),
+    }
+  }
+
+  // Reads `LOCKOUT_THRESHOLD`, `LOCKOUT_SECONDS`, `LOGIN_DELAY_SECONDS` and their `IP_` prefixed variants.
+  pub fn from_env() -> Result<LoginGuard, String> {
+    Ok(LoginGuard::new(LockoutPolicy::from_env("", 5)?, LockoutPolicy::from_env("IP_", 20)?))
+  }
+
+  pub fn window_seconds(&self) -> i64 {
+    self.account_policy.lockout_seconds.max(self.ip_policy.lockout_seconds)
+  }
+
+  pub fn load(&self, rows: Vec<LoginAttemptRow>) {
+    let mut attempts = self.attempts.lock().unwrap();
+    for (key, failures, blocked_until, last_failure) in rows {
+      attempts.insert(key, Attempts { failures, blocked_until, last_failure });
+    }
+  }
+
+  pub fn check(&self, username: &str, ip: &str) -> Result<(), ActixError> {
+    self.check_at(username, ip, Utc::now().timestamp())
+  }
+
+  pub fn record_failure(&self, db: &dyn Database, username: &str, ip: &str) {
+    for attempt in self.fail(username, ip, Utc::now().timestamp()) {
+      if let Err(err) = db.save_login_attempt(&attempt) {
+        eprintln!("Erreur lors de l'enregistrement de la tentative de connexion: {}", err);
+      }
+    }
+  }
+
+  fn check_at(&self, username: &str, ip: &str, now: i64) -> Result<(), ActixError> {
+    let attempts = self.attempts.lock().unwrap();
+
+    let blocked = [account_key(username), ip_key(ip)].iter()
+      .filter_map(|key| attempts.get(key))
+      .any(|attempt| attempt.blocked_until > now);
+
+    if blocked {
+      Err(ActixError::TooManyAttempts)
+    } else {
+      Ok(())
+    }
+  }
+
+  // Counts a failure against both keys and returns their updated rows. Failures older than the
+  // lockout are forgotten first.
+  fn fail(&self, username: &str, ip: &str, now: i64) -> Vec<LoginAttemptRow> {
+    let mut attempts = self.attempts.lock().unwrap();
+    attempts.retain(|key, attempt| attempt.blocked_until > now || now - attempt.last_failure <= self.policy(key).lockout_seconds);
+
+    [account_key(username), ip_key(ip)].into_iter()
+      .map(|key| {
+        let policy = self.policy(&key);
+        let attempt = attempts.entry(key.clone()).or_insert(Attempts { failures: 0, blocked_until: 0, last_failure: now });
+
+        attempt.failures += 1;
+        attempt.blocked_until = policy.blocked_until(attempt.failures, now);
+        attempt.last_failure = now;
+        (key, attempt.failures, attempt.blocked_until, attempt.last_failure)
+      })
+      .collect()
+  }
+
+  // The IP counter is left alone: logging into one's own account must not reset it.
+  pub fn record_success(&self, db: &dyn Database, username: &str) {
+    let key = account_key(username);
+    if self.attempts.lock().unwrap().remove(&key).is_none() {
+      return;
+    }
+
+    if let Err(err) = db.delete_login_attempt(&key) {
+      eprintln!("Erreur lors de la réinitialisation des tentatives de connexion: {}", err);
+    }
+  }
+
+  pub fn unlock(&self, db: &dyn Database, username: &str) -> Result<(), ActixError> {
+    let key = account_key(username);
+    self.attempts.lock().unwrap().remove(&key);
+
+    db.delete_login_attempt(&key)
+  }
+
+  fn policy(&self, key: &str) -> &LockoutPolicy {
+    if key.starts_with("ip:") {
+      &self.ip_policy
+    } else {
+      &self.account_policy
+    }
+  }
+}
+
+fn read_env<T: FromStr>(prefix: &str, name: &str, default: T) -> Result<T, String> {
+  match env::var(format!("{}{}", prefix, name)) {
+    Ok(value) => value.parse().map_err(|_| format!("{}{} doit être un nombre, pas {}", prefix, name, value)),
+    Err(_) => Ok(default),
+  }
+}
+
+fn account_key(username: &str) -> String {
+  format!("account:{}", username)
+}
+
+fn ip_key(ip: &str) -> String {
+  format!("ip:{}", ip)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn guard() -> LoginGuard {
+    LoginGuard::new(
+      LockoutPolicy { threshold: 3, lockout_seconds: 900, base_delay_seconds: 1 },
+      LockoutPolicy { threshold: 5, lockout_seconds: 600, base_delay_seconds: 1 },
+    )
+  }
+
+  #[test]
+  fn delays_double_until_the_threshold() {
+    let policy = LockoutPolicy { threshold: 5, lockout_seconds: 900, base_delay_seconds: 2 };
+    let delays: Vec<i64> = (1..=6).map(|failures| policy.blocked_until(failures, 1000) - 1000).collect();
+    assert_eq!(delays, vec![2, 4, 8, 16, 900, 900]);
+
+    let capped = LockoutPolicy { threshold: 50, lockout_seconds: 60, base_delay_seconds: 1 };
+    assert_eq!(capped.blocked_until(10, 0), 60);
+    assert_eq!(capped.blocked_until(49, 0), 60);
+  }
+
+  #[test]
+  fn zero_thresholds_are_rejected() {
+    assert!(LockoutPolicy { threshold: 0, lockout_seconds: 900, base_delay_seconds: 1 }.validate("IP_").is_err());
+    assert!(LockoutPolicy { threshold: 1, lockout_seconds: 900, base_delay_seconds: 1 }.validate("IP_").is_ok());
+  }
+
+  #[test]
+  fn accounts_are_locked_out_at_the_threshold() {
+    let guard = guard();
+    guard.fail("alice", "10.0.0.1", 0);
+    assert!(guard.check_at("alice", "10.0.0.1", 0).is_err());
+    assert!(guard.check_at("alice", "10.0.0.1", 1).is_ok());
+
+    guard.fail("alice", "10.0.0.1", 1);
+    let rows = guard.fail("alice", "10.0.0.1", 3);
+    assert!(rows.contains(&(String::from("account:alice"), 3, 903, 3)));
+    assert!(guard.check_at("alice", "10.0.0.2", 902).is_err());
+    assert!(guard.check_at("alice", "10.0.0.2", 903).is_ok());
+  }
+
+  #[test]
+  fn failures_are_forgotten_after_the_lockout() {
+    let guard = guard();
+    guard.fail("alice", "10.0.0.1", 0);
+    guard.fail("alice", "10.0.0.1", 1);
+
+    let rows = guard.fail("alice", "10.0.0.1", 1000);
+    assert!(rows.contains(&(String::from("account:alice"), 1, 1001, 1000)));
+    assert!(rows.contains(&(String::from("ip:10.0.0.1"), 1, 1001, 1000)));
+  }
+
+  #[test]
+  fn accounts_and_ips_are_counted_separately() {
+    let guard = guard();
+    for now in 0..3 {
+      guard.fail("alice", "10.0.0.1", now * 10);
+    }
+
+    // The account is locked from everywhere, the IP is only slowed down so far.
+    assert!(guard.check_at("alice", "10.0.0.2", 100).is_err());
+    assert!(guard.check_at("bob", "10.0.0.1", 100).is_ok());
+
+    guard.fail("bob", "10.0.0.1", 100);
+    guard.fail("carol", "10.0.0.1", 200);
+    assert!(guard.check_at("dave", "10.0.0.1", 700).is_err());
+    assert!(guard.check_at("dave", "10.0.0.2", 700).is_ok());
+    assert!(guard.check_at("bob", "10.0.0.2", 700).is_ok());
+  }
+}
@@ -0,0 +1,102 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use chrono::Utc;
+use lettre::{message::Mailbox, Message, SmtpTransport, Transport};
+use crate::errors::ActixError;
+
+// Accounts have no separate e-mail field: the username is used as the recipient address.
+pub trait Mailer: Send + Sync {
+  fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), ActixError>;
+}
+
+pub struct SmtpMailer {
+  transport: SmtpTransport,
+  from: Mailbox,
+}
+
+impl SmtpMailer {
+  pub fn new(url: &str, from: &str) -> Result<Self, String> {
+    let transport = SmtpTransport::from_url(url).map_err(|err| err.to_string())?.build();
+    let from = from.parse().map_err(|err: lettre::address::AddressError| err.to_string())?;
+
+    Ok(SmtpMailer { transport, from })
+  }
+}
+
+impl Mailer for SmtpMailer {
+  fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), ActixError> {
+    let to: Mailbox = to.parse().map_err(|err| {
+      eprintln!("Adresse e-mail invalide: {}", err);
+      ActixError::Unknown
+    })?;
+
+    let message = Message::builder()
+      .from(self.from.clone())
+      .to(to)
+      .subject(subject)
+      .body(body.to_string())
+      .map_err(|err| {
+        eprintln!("Erreur lors de la création de l'e-mail: {}", err);
+        ActixError::Unknown
+      })?;
+
+    self.transport.send(&message).map(|_| ()).map_err(|err| {
+      eprintln!("Erreur lors de l'envoi de l'e-mail: {}", err);
+      ActixError::Unknown
+    })
+  }
+}
+
+// Writes messages to a file, or to stdout when no path is given. Meant for tests and local development.
+pub struct LogMailer {
+  path: Option<PathBuf>,
+  lock: Mutex<()>,
+}
+
+impl LogMailer {
+  pub fn new(path: Option<PathBuf>) -> Self {
+    LogMailer { path, lock: Mutex::new(()) }
+  }
+}
+
+impl Mailer for LogMailer {
+  fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), ActixError> {
+    let entry = format!("[{}] To: {}\nSubject: {}\n\n{}\n\n", Utc::now().to_rfc3339(), to, subject, body);
+
+    match &self.path {
+      Some(path) => {
+        let _guard = self.lock.lock().unwrap();
+        OpenOptions::new()
+          .create(true)
+          .append(true)
+          .open(path)
+          .and_then(|mut file| file.write_all(entry.as_bytes()))
+          .map_err(|err| {
+            eprintln!("Erreur lors de l'écriture de l'e-mail: {}", err);
+            ActixError::Unknown
+          })
+      },
+      None => {
+        println!("{}", entry);
+        Ok(())
+      }
+    }
+  }
+}
+
+// The log mailer writes reset tokens in clear, so it is only used when asked for with `MAILER=log`.
+// Otherwise SMTP_URL and MAIL_FROM must be set.
+pub fn mailer_from_env() -> Result<Arc<dyn Mailer>, String> {
+  match env::var("MAILER").as_deref() {
+    Ok("log") => Ok(Arc::new(LogMailer::new(env::var("MAIL_LOG_FILE").ok().map(PathBuf::from)))),
+    Ok("smtp") | Err(_) => {
+      let url = env::var("SMTP_URL").map_err(|_| String::from("SMTP_URL doit être défini, ou MAILER=log"))?;
+      let from = env::var("MAIL_FROM").map_err(|_| String::from("MAIL_FROM doit être défini avec SMTP_URL"))?;
+      Ok(Arc::new(SmtpMailer::new(&url, &from)?))
+    },
+    Ok(mailer) => Err(format!("MAILER inconnu: {}", mailer)),
+  }
+}
@@ -2,41 +2,140 @@ use actix_cors::Cors;
 use actix_web::{middleware::Logger, web, App, HttpServer};
 use dotenv::dotenv;
 use std::env;
+use std::sync::Arc;
+use std::time::Duration;
 
-mod handlers;
-mod models;
-mod persistance;
-mod errors;
-mod accounts;
-mod jwt_check;
-mod rate_limit;
+use nyu_model::{
+    accounts, client_ip, handlers, jwt_check, lockout, mailer, migrations, password, persistance, purge, rate_limit, revocation, tokens,
+};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
 
-    env::var("TOKEN_SECRET").expect("TOKEN_SECRET must be set");
-    env::var("REFRESH_TOKEN_SECRET").expect("REFRESH_TOKEN_SECRET must be set");
-    let addr = env::var("ADDR_SERVER").expect("ADDR_SERVER must be set");
     let url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-
-    let pool = mysql::Pool::new(url.as_str()).unwrap_or_else(|err| {
+    let db = persistance::connect(&url).unwrap_or_else(|err| {
         eprintln!("Erreur lors de la connexion à la base de données: {}", err);
         std::process::exit(1);
     });
-    let db = web::Data::new(pool);
-    let accounts = accounts::AccountDB::new();
 
-    {
-        let all_accounts = persistance::accounts::get_account_data(&db).unwrap();
-        for account in all_accounts {
-            accounts.add_account(account).unwrap_or_else(|err| {
-                eprintln!("Erreur lors de la récupération des comptes: {}", err);
-                std::process::exit(1);
-            });
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        if command != "migrate" {
+            eprintln!("Commande inconnue: {} (usage: {})", command, migrations::USAGE);
+            std::process::exit(2);
+        }
+        let migrate_args = migrations::MigrateArgs::parse(&args[1..]).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        });
+        if let Err(err) = migrations::run(&*db, &migrate_args) {
+            eprintln!("Erreur lors de la migration de la base de données: {}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if env::var("MIGRATE_ON_STARTUP").map(|migrate| migrate != "false").unwrap_or(true) {
+        let migrate_args = migrations::MigrateArgs { command: migrations::MigrateCommand::Up, dry_run: false };
+        if let Err(err) = migrations::run(&*db, &migrate_args) {
+            eprintln!("Erreur lors de la migration de la base de données: {}", err);
+            std::process::exit(1);
         }
     }
 
+    let token_keys = tokens::TokenKeys::from_env().unwrap_or_else(|err| {
+        eprintln!("Erreur lors de la configuration des jetons: {}", err);
+        std::process::exit(1);
+    });
+    let addr = env::var("ADDR_SERVER").expect("ADDR_SERVER must be set");
+    let retention_days = purge::retention_days_from_env().unwrap_or_else(|err| {
+        eprintln!("Erreur lors de la configuration de la purge des comptes: {}", err);
+        std::process::exit(1);
+    });
+    let cache_settings = accounts::CacheSettings::from_env().unwrap_or_else(|err| {
+        eprintln!("Erreur lors de la configuration du cache des comptes: {}", err);
+        std::process::exit(1);
+    });
+
+    let (accounts, loaded_at) = db.now()
+        .and_then(|loaded_at| {
+            let accounts = match cache_settings.mode {
+                accounts::CacheMode::Eager => accounts::CachedAccountStore::load(db.clone())?,
+                accounts::CacheMode::Lazy { capacity, ttl } => accounts::CachedAccountStore::lazy(db.clone(), capacity, ttl),
+            };
+            Ok((accounts, loaded_at))
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("Erreur lors de la récupération des comptes: {}", err);
+            std::process::exit(1);
+        });
+    let account_events = env::var("ACCOUNT_EVENTS_REDIS_URL").ok().map(|url| {
+        Arc::new(accounts::RedisAccountEvents::new(&url).unwrap_or_else(|err| {
+            eprintln!("Erreur lors de la configuration des notifications de comptes: {}", err);
+            std::process::exit(1);
+        }))
+    });
+    let accounts = Arc::new(match &account_events {
+        Some(events) => accounts.with_events(events.clone()),
+        None => accounts,
+    });
+    if let Some(events) = &account_events {
+        events.subscribe(accounts.clone());
+    }
+    accounts.clone().spawn_refresh(loaded_at, cache_settings.refresh_period);
+    accounts.clone().spawn_stats_report(Duration::from_secs(300));
+    let accounts: web::Data<dyn accounts::AccountStore> = web::Data::from(accounts as Arc<dyn accounts::AccountStore>);
+    let credentials = web::Data::new(password::Credentials::new());
+    let db: web::Data<dyn persistance::Database> = web::Data::from(db);
+
+    let revocations = revocation::RevocationList::new();
+    {
+        let revoked_tokens = db.get_revoked_access_tokens().unwrap_or_else(|err| {
+            eprintln!("Erreur lors de la récupération des révocations: {}", err);
+            std::process::exit(1);
+        });
+        revocations.extend(revoked_tokens);
+    }
+    revocations.spawn_sync(db.clone(), Duration::from_secs(30));
+    purge::spawn_account_purge(db.clone(), retention_days, Duration::from_secs(3600));
+
+    let login_guard = lockout::LoginGuard::from_env().unwrap_or_else(|err| {
+        eprintln!("Erreur lors de la configuration du verrouillage des comptes: {}", err);
+        std::process::exit(1);
+    });
+    {
+        let login_attempts = db.get_login_attempts(login_guard.window_seconds()).unwrap_or_else(|err| {
+            eprintln!("Erreur lors de la récupération des tentatives de connexion: {}", err);
+            std::process::exit(1);
+        });
+        login_guard.load(login_attempts);
+    }
+
+    let mailer = web::Data::from(mailer::mailer_from_env().unwrap_or_else(|err| {
+        eprintln!("Erreur lors de la configuration des e-mails: {}", err);
+        std::process::exit(1);
+    }));
+    let trusted_proxies = client_ip::TrustedProxies::from_env().unwrap_or_else(|err| {
+        eprintln!("Erreur lors de la configuration des proxies: {}", err);
+        std::process::exit(1);
+    });
+    let rate_limit_policies = rate_limit::RateLimitPolicies::from_env().unwrap_or_else(|err| {
+        eprintln!("Erreur lors de la configuration de la limitation: {}", err);
+        std::process::exit(1);
+    });
+    let rate_limit_key = rate_limit::RateLimitKey::from_env().unwrap_or_else(|err| {
+        eprintln!("Erreur lors de la configuration de la limitation: {}", err);
+        std::process::exit(1);
+    });
+    let rate_limit = rate_limit::RateLimit::new(
+        rate_limit_policies,
+        rate_limit_key,
+        token_keys.clone(),
+        trusted_proxies.clone(),
+    )
+    .shared_store_from_env();
+
     println!("🚀 Server started successfully");
     HttpServer::new(move || {
         App::new()
@@ -50,25 +149,24 @@ async fn main() -> std::io::Result<()> {
                         actix_web::http::header::ACCEPT,
                     ])
                     .allowed_header(actix_web::http::header::CONTENT_TYPE)
+                    .expose_headers(vec![
+                        "X-RateLimit-Limit",
+                        "X-RateLimit-Remaining",
+                        "X-RateLimit-Reset",
+                        "Retry-After",
+                    ])
                     .max_age(3600),
             )
-            .wrap(rate_limit::RateLimit::new(10))
-            .app_data(web::Data::new(accounts.clone()))
+            .wrap(rate_limit.clone())
+            .app_data(accounts.clone())
+            .app_data(credentials.clone())
+            .app_data(web::Data::new(revocations.clone()))
+            .app_data(web::Data::new(login_guard.clone()))
             .app_data(db.clone())
-            .service(handlers::healthcheck::health_checker_handler)
-            .service(
-                web::scope("/v1")
-                .service(handlers::accounts::accounts_create_handler)
-            )
-            .service(handlers::login::login_handler)    
-            .service(
-                web::scope("/v1")
-                    .wrap(jwt_check::JwtCheck)
-                    .service(handlers::accounts::accounts_list_handler)
-                    .service(handlers::accounts::account_get_handler)
-                    .service(handlers::accounts::account_update_handler)
-            )
-            
+            .app_data(mailer.clone())
+            .app_data(web::Data::new(trusted_proxies.clone()))
+            .app_data(web::Data::new(token_keys.clone()))
+            .configure(|cfg| handlers::configure(cfg, jwt_check::JwtCheck::new(revocations.clone(), token_keys.clone())))
     })
     .bind(addr)?
     .workers(2)
@@ -0,0 +1,264 @@
+use sha2::{Digest, Sha256};
+use crate::persistance::Database;
+
+// The MySQL database itself is created by hand, with `sql/0_create_database.sql`.
+macro_rules! migration {
+  ($version:literal, $name:literal) => {
+    Migration {
+      version: $version,
+      name: $name,
+      mysql: Script {
+        up: include_str!(concat!("../sql/migrations/mysql/", $name, ".up.sql")),
+        down: include_str!(concat!("../sql/migrations/mysql/", $name, ".down.sql")),
+      },
+      sqlite: Script {
+        up: include_str!(concat!("../sql/migrations/sqlite/", $name, ".up.sql")),
+        down: include_str!(concat!("../sql/migrations/sqlite/", $name, ".down.sql")),
+      },
+      postgres: Script {
+        up: include_str!(concat!("../sql/migrations/postgres/", $name, ".up.sql")),
+        down: include_str!(concat!("../sql/migrations/postgres/", $name, ".down.sql")),
+      },
+    }
+  };
+}
+
+// Every migration of `sql/migrations`, by version. Each backend has its own script for the same
+// schema change. Applied migrations must not be edited: add a new one instead.
+pub static MIGRATIONS: &[Migration] = &[
+  migration!(1, "0001_account"),
+  migration!(2, "0002_access_token"),
+  migration!(3, "0003_account_password"),
+  migration!(4, "0004_access_token_rotation"),
+  migration!(5, "0005_access_token_revocation"),
+  migration!(6, "0006_roles"),
+  migration!(7, "0007_access_token_length"),
+  migration!(8, "0008_account_soft_delete"),
+  migration!(9, "0009_password_reset"),
+  migration!(10, "0010_two_factor"),
+  migration!(11, "0011_login_attempts"),
+  migration!(12, "0012_account_username_unique"),
+  migration!(13, "0013_account_updated_at"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dialect {
+  Mysql,
+  Sqlite,
+  Postgres,
+}
+
+pub struct Script {
+  pub up: &'static str,
+  pub down: &'static str,
+}
+
+pub struct Migration {
+  pub version: u32,
+  pub name: &'static str,
+  pub mysql: Script,
+  pub sqlite: Script,
+  pub postgres: Script,
+}
+
+impl Migration {
+  pub fn script(&self, dialect: Dialect) -> &Script {
+    match dialect {
+      Dialect::Mysql => &self.mysql,
+      Dialect::Sqlite => &self.sqlite,
+      Dialect::Postgres => &self.postgres,
+    }
+  }
+
+  pub fn checksum(&self, dialect: Dialect) -> String {
+    format!("{:x}", Sha256::digest(self.script(dialect).up.as_bytes()))
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MigrateCommand {
+  Up,
+  // Reverts this many of the last applied migrations.
+  Down(usize),
+  Status,
+  // Records every migration up to this version as applied, without running them.
+  Baseline(u32),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MigrateArgs {
+  pub command: MigrateCommand,
+  // Prints what would run instead of running it.
+  pub dry_run: bool,
+}
+
+// Databases set up by hand from the scripts of `sql/`, before migrations existed, have tables but
+// no recorded migrations, and `up` refuses to run on them, at startup as well. Such a database is
+// adopted with `migrate baseline 2` when it has the original schema (0001 and 0002), or with the
+// version of the last script run by hand.
+pub const USAGE: &str = "migrate [up | down [steps] | status | baseline <version>] [--dry-run]";
+
+impl MigrateArgs {
+  pub fn parse(args: &[String]) -> Result<MigrateArgs, String> {
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let args: Vec<&str> = args.iter().map(String::as_str).filter(|arg| *arg != "--dry-run").collect();
+
+    let command = match args.as_slice() {
+      [] | ["up"] => MigrateCommand::Up,
+      ["down"] => MigrateCommand::Down(1),
+      ["down", steps] => MigrateCommand::Down(steps.parse().map_err(|_| format!("nombre d'étapes invalide: {}", steps))?),
+      ["status"] => MigrateCommand::Status,
+      ["baseline", version] => MigrateCommand::Baseline(version.parse().map_err(|_| format!("version invalide: {}", version))?),
+      _ => return Err(format!("usage: {}", USAGE)),
+    };
+
+    Ok(MigrateArgs { command, dry_run })
+  }
+}
+
+// Migrations not applied yet, by version. Refused when an applied migration was edited or is
+// unknown to this build.
+pub fn pending(applied: &[(u32, String)], dialect: Dialect) -> Result<Vec<&'static Migration>, String> {
+  for (version, checksum) in applied {
+    match MIGRATIONS.iter().find(|migration| migration.version == *version) {
+      Some(migration) if migration.checksum(dialect) == *checksum => {}
+      Some(migration) => return Err(format!("la migration {} a été modifiée après avoir été appliquée", migration.name)),
+      None => return Err(format!("la migration {} appliquée est inconnue", version)),
+    }
+  }
+
+  Ok(MIGRATIONS.iter().filter(|migration| !applied.iter().any(|(version, _)| *version == migration.version)).collect())
+}
+
+// The last `steps` applied migrations, the latest first.
+pub fn to_revert(applied: &[(u32, String)], dialect: Dialect, steps: usize) -> Result<Vec<&'static Migration>, String> {
+  pending(applied, dialect)?;
+
+  let mut versions: Vec<u32> = applied.iter().map(|(version, _)| *version).collect();
+  versions.sort_unstable_by(|a, b| b.cmp(a));
+
+  Ok(versions.into_iter()
+    .take(steps)
+    .filter_map(|version| MIGRATIONS.iter().find(|migration| migration.version == version))
+    .collect())
+}
+
+// Runs under the backend's migration lock, so concurrent runs wait for each other instead of
+// applying the same migrations twice. MySQL migrations are not atomic though: its schema changes
+// commit on their own, and a migration failing halfway is left partly applied and unrecorded,
+// to be repaired by hand.
+pub fn run(db: &dyn Database, args: &MigrateArgs) -> Result<(), String> {
+  db.lock_migrations().map_err(|err| format!("verrou des migrations: {}", err))?;
+  let result = run_locked(db, args);
+  let unlocked = db.unlock_migrations().map_err(|err| format!("verrou des migrations: {}", err));
+
+  result.and(unlocked)
+}
+
+fn run_locked(db: &dyn Database, args: &MigrateArgs) -> Result<(), String> {
+  db.create_migrations_table().map_err(|err| err.to_string())?;
+  let applied = db.get_applied_migrations().map_err(|err| err.to_string())?;
+  let dialect = db.dialect();
+
+  match args.command {
+    MigrateCommand::Up => {
+      if applied.is_empty() && db.has_table("accounts").map_err(|err| err.to_string())? {
+        return Err(String::from(
+          "la base a déjà des tables mais aucune migration enregistrée: l'adopter avec `migrate baseline 2` (schéma d'origine) ou la version du dernier script appliqué à la main",
+        ));
+      }
+      for migration in pending(&applied, dialect)? {
+        if args.dry_run {
+          println!("-- {} (simulation)\n{}\n", migration.name, migration.script(dialect).up);
+          continue;
+        }
+        db.apply_migration(migration).map_err(|err| format!("{}: {}", migration.name, err))?;
+        println!("Migration {} appliquée", migration.name);
+      }
+    },
+    MigrateCommand::Down(steps) => {
+      for migration in to_revert(&applied, dialect, steps)? {
+        if args.dry_run {
+          println!("-- {} (simulation)\n{}\n", migration.name, migration.script(dialect).down);
+          continue;
+        }
+        db.revert_migration(migration).map_err(|err| format!("{}: {}", migration.name, err))?;
+        println!("Migration {} annulée", migration.name);
+      }
+    },
+    MigrateCommand::Status => {
+      let pending = pending(&applied, dialect)?;
+      for migration in MIGRATIONS {
+        let state = if pending.iter().any(|other| other.version == migration.version) { "en attente" } else { "appliquée" };
+        println!("{} {}", migration.name, state);
+      }
+    },
+    MigrateCommand::Baseline(version) => {
+      for migration in pending(&applied, dialect)?.into_iter().filter(|migration| migration.version <= version) {
+        if !args.dry_run {
+          db.mark_migration_applied(migration).map_err(|err| format!("{}: {}", migration.name, err))?;
+        }
+        println!("Migration {} marquée comme appliquée", migration.name);
+      }
+    },
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn applied(versions: &[u32]) -> Vec<(u32, String)> {
+    versions.iter()
+      .map(|version| (*version, MIGRATIONS[*version as usize - 1].checksum(Dialect::Mysql)))
+      .collect()
+  }
+
+  fn args(args: &[&str]) -> Result<MigrateArgs, String> {
+    MigrateArgs::parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+  }
+
+  #[test]
+  fn migrations_are_numbered_in_order() {
+    for (index, migration) in MIGRATIONS.iter().enumerate() {
+      assert_eq!(migration.version as usize, index + 1);
+      assert!(migration.name.starts_with(&format!("{:04}_", migration.version)));
+    }
+  }
+
+  #[test]
+  fn pending_migrations_follow_the_applied_ones() {
+    let pending: Vec<u32> = pending(&applied(&[1, 2, 3]), Dialect::Mysql).unwrap().iter().map(|migration| migration.version).collect();
+    assert_eq!(pending, (4..=MIGRATIONS.len() as u32).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn edited_or_unknown_migrations_are_refused() {
+    let mut edited = applied(&[1, 2]);
+    edited[1].1 = String::from("0");
+    assert!(pending(&edited, Dialect::Mysql).is_err());
+    // Checksums are those of the backend's own scripts.
+    assert!(pending(&applied(&[1, 2]), Dialect::Sqlite).is_err());
+
+    assert!(pending(&[(999, String::new())], Dialect::Mysql).is_err());
+  }
+
+  #[test]
+  fn the_latest_migrations_are_reverted_first() {
+    let reverted: Vec<u32> = to_revert(&applied(&[1, 2, 3]), Dialect::Mysql, 2).unwrap().iter().map(|migration| migration.version).collect();
+    assert_eq!(reverted, vec![3, 2]);
+    assert!(to_revert(&[], Dialect::Mysql, 1).unwrap().is_empty());
+  }
+
+  #[test]
+  fn arguments_are_parsed() {
+    assert_eq!(args(&[]).unwrap(), MigrateArgs { command: MigrateCommand::Up, dry_run: false });
+    assert_eq!(args(&["down", "--dry-run"]).unwrap(), MigrateArgs { command: MigrateCommand::Down(1), dry_run: true });
+    assert_eq!(args(&["down", "3"]).unwrap().command, MigrateCommand::Down(3));
+    assert_eq!(args(&["baseline", "12"]).unwrap().command, MigrateCommand::Baseline(12));
+    assert!(args(&["down", "many"]).is_err());
+    assert!(args(&["sideways"]).is_err());
+  }
+}
@@ -22,13 +22,6 @@ pub struct QueryPageOptions {
     pub limit: Option<usize>,
 }
 
-#[derive(Serialize, Debug)]
-pub struct TodoListResponse {
-    pub status: String,
-    pub results: usize,
-    pub todos: Vec<Account>,
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BasicResponse {
     pub data: String,
@@ -46,28 +39,100 @@ pub struct UpdateAccountData {
     pub username: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdatePasswordData {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordData {
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordData {
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetForgottenPasswordData {
+    pub token: String,
+    pub new_password: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BasicResponseError {
     pub error: String,
     pub code: u16,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub id_account: u64,
-    pub admin: bool,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
     pub exp: i64,
+    pub jti: String,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct AuthorizationMiddleware {
-    pub user_id: String,
-    pub admin: bool,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshClaims {
+    pub id_account: u64,
+    pub exp: i64,
+    pub jti: String,
 }
 
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChallengeClaims {
+    pub id_account: u64,
+    pub aud: String,
+    pub exp: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountTotp {
+    pub id_account: u64,
+    pub secret: String,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TwoFactorEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TwoFactorChallenge {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorCodeData {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginTwoFactorData {
+    pub challenge_token: String,
+    pub code: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LoginData {
     pub username: String,
     pub password: String,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenData {
+    pub refresh_token: String,
+}
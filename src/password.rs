@@ -0,0 +1,233 @@
+use argon2::{
+  password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher as _, PasswordVerifier, SaltString},
+  Algorithm, Argon2, Params, Version,
+};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use crate::errors::ActixError;
+
+#[derive(Debug, PartialEq)]
+pub enum PasswordCheck {
+  Valid,
+  // The password matches but the stored hash should be replaced by a fresh one.
+  Outdated,
+  Invalid,
+}
+
+pub trait PasswordHasher: Send + Sync {
+  fn hash(&self, username: &str, password: &str) -> Result<String, ActixError>;
+  fn verify(&self, username: &str, password: &str, hash: &str) -> PasswordCheck;
+}
+
+pub struct Argon2Hasher {
+  params: Params,
+}
+
+impl Argon2Hasher {
+  pub fn new(params: Params) -> Self {
+    Argon2Hasher { params }
+  }
+
+  fn argon2(&self) -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+  }
+
+  // Parsed parameters always carry an output length, which ours may leave unset, so only the
+  // costs are compared.
+  fn is_current(&self, hash: &PasswordHash) -> bool {
+    let costs = |params: &Params| (params.m_cost(), params.t_cost(), params.p_cost());
+
+    hash.algorithm == Algorithm::Argon2id.ident()
+      && hash.version == Some(Version::V0x13.into())
+      && Params::try_from(hash).map(|params| costs(&params) == costs(&self.params)).unwrap_or(false)
+  }
+}
+
+impl Default for Argon2Hasher {
+  fn default() -> Self {
+    Argon2Hasher::new(Params::default())
+  }
+}
+
+impl PasswordHasher for Argon2Hasher {
+  fn hash(&self, _username: &str, password: &str) -> Result<String, ActixError> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    self.argon2()
+      .hash_password(password.as_bytes(), &salt)
+      .map(|hash| hash.to_string())
+      .map_err(|_| ActixError::Unknown)
+  }
+
+  fn verify(&self, username: &str, password: &str, hash: &str) -> PasswordCheck {
+    if Md5Hasher::is_md5(hash) {
+      return match Md5Hasher.verify(username, password, hash) {
+        PasswordCheck::Invalid => PasswordCheck::Invalid,
+        _ => PasswordCheck::Outdated,
+      };
+    }
+
+    let parsed_hash = match PasswordHash::new(hash) {
+      Ok(parsed_hash) => parsed_hash,
+      Err(_) => return PasswordCheck::Invalid,
+    };
+
+    if self.argon2().verify_password(password.as_bytes(), &parsed_hash).is_err() {
+      return PasswordCheck::Invalid;
+    }
+
+    if self.is_current(&parsed_hash) {
+      PasswordCheck::Valid
+    } else {
+      PasswordCheck::Outdated
+    }
+  }
+}
+
+// Legacy `md5("username:password")` scheme, only kept to recognise hashes stored before Argon2.
+pub struct Md5Hasher;
+
+impl Md5Hasher {
+  fn is_md5(hash: &str) -> bool {
+    hash.len() == 32 && hash.chars().all(|c| c.is_ascii_hexdigit())
+  }
+}
+
+impl PasswordHasher for Md5Hasher {
+  fn hash(&self, username: &str, password: &str) -> Result<String, ActixError> {
+    let digest = md5::compute(format!("{}:{}", username, password));
+    Ok(format!("{:x}", digest))
+  }
+
+  fn verify(&self, username: &str, password: &str, hash: &str) -> PasswordCheck {
+    match self.hash(username, password) {
+      Ok(computed) if constant_time_eq(computed.as_bytes(), hash.as_bytes()) => PasswordCheck::Valid,
+      _ => PasswordCheck::Invalid,
+    }
+  }
+}
+
+// Hashes and checks account passwords, whichever store the accounts live in.
+#[derive(Clone)]
+pub struct Credentials {
+  hasher: Arc<dyn PasswordHasher>,
+  // Verified against when the username is unknown, so both cases cost the same time.
+  dummy_hash: Arc<String>,
+}
+
+impl Credentials {
+  pub fn new() -> Credentials {
+    Credentials::with_hasher(Arc::new(Argon2Hasher::default()))
+  }
+
+  pub fn with_hasher(hasher: Arc<dyn PasswordHasher>) -> Credentials {
+    let dummy_hash = hasher.hash("", &random_token(32)).unwrap_or_default();
+
+    Credentials {
+      hasher,
+      dummy_hash: Arc::new(dummy_hash),
+    }
+  }
+
+  pub fn hash(&self, username: &str, password: &str) -> Result<String, ActixError> {
+    self.hasher.hash(username, password)
+  }
+
+  // `hash` is `None` when no account has this username.
+  pub fn verify(&self, username: &str, password: &str, hash: Option<&str>) -> PasswordCheck {
+    match hash {
+      Some(hash) => self.hasher.verify(username, password, hash),
+      None => {
+        self.hasher.verify(username, password, &self.dummy_hash);
+        PasswordCheck::Invalid
+      }
+    }
+  }
+}
+
+impl Default for Credentials {
+  fn default() -> Self {
+    Credentials::new()
+  }
+}
+
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+pub fn random_token(len: usize) -> String {
+  rand::thread_rng()
+    .sample_iter(&Alphanumeric)
+    .take(len)
+    .map(char::from)
+    .collect()
+}
+
+// Single-use secrets (reset tokens...) are high-entropy, so a plain SHA-256 is enough to store them.
+pub fn hash_token(token: &str) -> String {
+  format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Cheap parameters keep the tests fast; only `is_current` cares about their values.
+  fn hasher() -> Argon2Hasher {
+    Argon2Hasher::new(Params::new(8, 1, 1, None).unwrap())
+  }
+
+  #[test]
+  fn argon2_hashes_verify() {
+    let hasher = hasher();
+    let hash = hasher.hash("alice", "secret").unwrap();
+
+    assert!(hash.starts_with("$argon2id$"));
+    assert_eq!(hasher.verify("alice", "secret", &hash), PasswordCheck::Valid);
+    assert_eq!(hasher.verify("alice", "wrong", &hash), PasswordCheck::Invalid);
+    assert_eq!(hasher.verify("alice", "secret", "not a hash"), PasswordCheck::Invalid);
+  }
+
+  #[test]
+  fn hashes_made_with_other_parameters_are_outdated() {
+    let hash = Argon2Hasher::new(Params::new(16, 1, 1, None).unwrap()).hash("alice", "secret").unwrap();
+
+    assert_eq!(hasher().verify("alice", "secret", &hash), PasswordCheck::Outdated);
+    assert_eq!(hasher().verify("alice", "wrong", &hash), PasswordCheck::Invalid);
+  }
+
+  #[test]
+  fn hashes_made_with_the_default_parameters_are_current() {
+    let hasher = Argon2Hasher::default();
+    let hash = hasher.hash("alice", "secret").unwrap();
+
+    assert_eq!(hasher.verify("alice", "secret", &hash), PasswordCheck::Valid);
+  }
+
+  #[test]
+  fn legacy_md5_hashes_are_outdated() {
+    let hash = format!("{:x}", md5::compute("alice:secret"));
+
+    assert_eq!(hasher().verify("alice", "secret", &hash), PasswordCheck::Outdated);
+    assert_eq!(hasher().verify("alice", "wrong", &hash), PasswordCheck::Invalid);
+    assert_eq!(hasher().verify("bob", "secret", &hash), PasswordCheck::Invalid);
+  }
+
+  #[test]
+  fn unknown_usernames_are_invalid() {
+    let credentials = Credentials::with_hasher(Arc::new(hasher()));
+
+    assert_eq!(credentials.verify("alice", "", None), PasswordCheck::Invalid);
+  }
+
+  #[test]
+  fn constant_time_eq_compares_lengths() {
+    assert!(constant_time_eq(b"", b""));
+    assert!(constant_time_eq(b"abc", b"abc"));
+    assert!(!constant_time_eq(b"abc", b"abd"));
+    assert!(!constant_time_eq(b"abc", b"abcd"));
+    assert!(!constant_time_eq(b"abcd", b"abc"));
+    assert!(!constant_time_eq(b"", b"a"));
+  }
+}
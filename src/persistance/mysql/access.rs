@@ -0,0 +1,172 @@
+use crate::models::AccessToken;
+use crate::persistance::RefreshTokenRow;
+use crate::tokens::ACCESS_TOKEN_LIFETIME_MINUTES;
+use crate::errors::ActixError;
+use mysql::prelude::Queryable;
+use mysql::params;
+
+pub fn insert_access_token_data(
+    pool: &mysql::Pool,
+    id_account: u64,
+    access_token: &str,
+    refresh_token: &str,
+    family: &str,
+) -> Result<AccessToken, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    let last_insert_id = insert_access_token(&mut conn, id_account, access_token, refresh_token, family)?;
+    if last_insert_id > 0 {
+        Ok(AccessToken{
+            id: last_insert_id,
+            access_token: access_token.to_string(),
+            id_account,
+            refresh_token: refresh_token.to_string(),
+        })
+    } else {
+        Err(ActixError::Unknown)
+    }
+}
+
+pub fn get_refresh_token(pool: &mysql::Pool, refresh_token: &str) -> Result<Option<RefreshTokenRow>, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(select_refresh_token(&mut conn, refresh_token)?)
+}
+
+pub fn revoke_token_pair(pool: &mysql::Pool, id: u64) -> Result<bool, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(revoke_access_token(&mut conn, id)? > 0)
+}
+
+pub fn revoke_token_family_data(pool: &mysql::Pool, family: &str) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    revoke_token_family(&mut conn, family)?;
+    Ok(())
+}
+
+pub fn revoke_access_token_data(pool: &mysql::Pool, access_token: &str) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(update_revoked_access_token(&mut conn, access_token)?)
+}
+
+pub fn revoke_account_tokens(pool: &mysql::Pool, id_account: u64) -> Result<Vec<(String, i64)>, ActixError> {
+    let mut conn = pool.get_conn()?;
+    let mut tx = conn.start_transaction(mysql::TxOpts::default())?;
+
+    let active_tokens = select_active_access_tokens(&mut tx, id_account)?;
+    revoke_account_access_tokens(&mut tx, id_account)?;
+    tx.commit()?;
+
+    Ok(active_tokens)
+}
+
+pub fn get_revoked_access_tokens(pool: &mysql::Pool) -> Result<Vec<(String, i64)>, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(select_revoked_access_tokens(&mut conn)?)
+}
+
+fn insert_access_token(
+    conn: &mut mysql::PooledConn,
+    id_account: u64,
+    access_token: &str,
+    refresh_token: &str,
+    family: &str,
+) -> mysql::error::Result<u64> {
+    conn.exec_drop("INSERT INTO access_token (id_account,access_token,refresh_token,family) VALUES (:id_account,:access_token,:refresh_token,:family)", params! {
+      "id_account" => id_account,
+      "access_token" => access_token,
+      "refresh_token" => refresh_token,
+      "family" => family,
+    },
+  ).map(|_| conn.last_insert_id())
+}
+
+fn select_refresh_token(
+    conn: &mut mysql::PooledConn,
+    refresh_token: &str,
+) -> mysql::error::Result<Option<RefreshTokenRow>> {
+    conn.exec_first(
+        "SELECT id, id_account, family, revoked_at IS NOT NULL FROM access_token WHERE refresh_token = :refresh_token",
+        params! {
+            "refresh_token" => refresh_token,
+        },
+    )
+}
+
+fn revoke_access_token(
+    conn: &mut mysql::PooledConn,
+    id: u64,
+) -> mysql::error::Result<u64> {
+    conn.exec_drop(
+        "UPDATE access_token SET revoked_at = NOW() WHERE id = :id AND revoked_at IS NULL",
+        params! {
+            "id" => id,
+        },
+    ).map(|_| conn.affected_rows())
+}
+
+fn revoke_token_family(
+    conn: &mut mysql::PooledConn,
+    family: &str,
+) -> mysql::error::Result<u64> {
+    conn.exec_drop(
+        "UPDATE access_token SET revoked_at = NOW() WHERE family = :family AND revoked_at IS NULL",
+        params! {
+            "family" => family,
+        },
+    ).map(|_| conn.affected_rows())
+}
+
+fn update_revoked_access_token(
+    conn: &mut mysql::PooledConn,
+    access_token: &str,
+) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        "UPDATE access_token SET revoked_at = NOW() WHERE access_token = :access_token AND revoked_at IS NULL",
+        params! {
+            "access_token" => access_token,
+        },
+    )
+}
+
+fn revoke_account_access_tokens(
+    conn: &mut impl Queryable,
+    id_account: u64,
+) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        "UPDATE access_token SET revoked_at = NOW() WHERE id_account = :id_account AND revoked_at IS NULL",
+        params! {
+            "id_account" => id_account,
+        },
+    )
+}
+
+fn select_active_access_tokens(
+    conn: &mut impl Queryable,
+    id_account: u64,
+) -> mysql::error::Result<Vec<(String, i64)>> {
+    conn.exec(
+        "SELECT access_token, UNIX_TIMESTAMP(date_added) + :lifetime FROM access_token
+         WHERE id_account = :id_account AND revoked_at IS NULL AND date_added > NOW() - INTERVAL :minutes MINUTE",
+        params! {
+            "id_account" => id_account,
+            "lifetime" => ACCESS_TOKEN_LIFETIME_MINUTES * 60,
+            "minutes" => ACCESS_TOKEN_LIFETIME_MINUTES,
+        },
+    )
+}
+
+fn select_revoked_access_tokens(conn: &mut mysql::PooledConn) -> mysql::error::Result<Vec<(String, i64)>> {
+    conn.exec(
+        "SELECT access_token, UNIX_TIMESTAMP(date_added) + :lifetime FROM access_token
+         WHERE revoked_at IS NOT NULL AND date_added > NOW() - INTERVAL :minutes MINUTE",
+        params! {
+            "lifetime" => ACCESS_TOKEN_LIFETIME_MINUTES * 60,
+            "minutes" => ACCESS_TOKEN_LIFETIME_MINUTES,
+        },
+    )
+}
@@ -0,0 +1,317 @@
+use crate::models::Account;
+use crate::errors::ActixError;
+use crate::persistance::DEFAULT_ROLE;
+use super::roles::insert_account_role;
+use mysql::prelude::Queryable;
+use mysql::params;
+
+const ER_DUP_ENTRY: u16 = 1062;
+
+pub fn create_new_account(
+    pool: &mysql::Pool,
+    username: &str,
+    hash_password: &str
+) -> Result<Account, ActixError> {
+    let mut conn = pool.get_conn()?;
+    let mut tx = conn.start_transaction(mysql::TxOpts::default())?;
+
+    let last_insert_id = insert_account_data(&mut tx, username, hash_password).map_err(duplicate_username)?;
+
+    if last_insert_id > 0 {
+        insert_account_role(&mut tx, last_insert_id, DEFAULT_ROLE)?;
+        tx.commit()?;
+
+        Ok(Account{
+            id: last_insert_id,
+            password: String::from(hash_password),
+            username: String::from(username),
+        })
+    } else {
+        Err(ActixError::Unknown)
+    }
+}
+
+pub fn get_account_data(pool: &mysql::Pool, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(select_account_details(&mut conn, offset as u64, limit as u64)?)
+}
+
+pub fn get_account_by_id_data(pool: &mysql::Pool, id_account: u64) -> Result<Account, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    select_account_by_id(&mut conn, id_account)?.ok_or(ActixError::NotFound)
+}
+
+pub fn get_account_by_username_data(pool: &mysql::Pool, username: &str) -> Result<Account, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    select_account_by_username(&mut conn, username)?.ok_or(ActixError::NotFound)
+}
+
+pub fn update_account_username_data(pool: &mysql::Pool, id_account: u64, username: &str) -> Result<Account, ActixError> {
+    let mut conn = pool.get_conn()?;
+    let mut tx = conn.start_transaction(mysql::TxOpts::default())?;
+
+    update_account_column(&mut tx, id_account, "username", username).map_err(duplicate_username)?;
+    let account = select_account_by_id(&mut tx, id_account)?.ok_or(ActixError::NotFound)?;
+    tx.commit()?;
+
+    Ok(account)
+}
+
+pub fn update_account_password_data(pool: &mysql::Pool, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+    let mut conn = pool.get_conn()?;
+    let mut tx = conn.start_transaction(mysql::TxOpts::default())?;
+
+    let account = update_account_password(&mut tx, id_account, hash_password)?;
+    tx.commit()?;
+
+    Ok(account)
+}
+
+// Meant to run inside a transaction, which the caller commits.
+pub fn update_account_password(conn: &mut impl Queryable, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+    update_account_column(conn, id_account, "password", hash_password)?;
+
+    select_account_by_id(conn, id_account)?.ok_or(ActixError::NotFound)
+}
+
+pub fn delete_account_data(pool: &mysql::Pool, id_account: u64) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    match soft_delete_account(&mut conn, id_account)? {
+        0 => Err(ActixError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+pub fn get_database_time(pool: &mysql::Pool) -> Result<i64, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(select_database_time(&mut conn)?)
+}
+
+// Accounts whose row changed at or after `since` (seconds since epoch) with whether they are
+// deleted, and the database time the lookup was made at.
+pub fn get_account_changes(pool: &mysql::Pool, since: i64) -> Result<(Vec<(Account, bool)>, i64), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    let now = select_database_time(&mut conn)?;
+    let changes = select_account_changes(&mut conn, since)?;
+
+    Ok((changes, now))
+}
+
+// Refused when another account took the username in the meantime.
+pub fn restore_account_data(pool: &mysql::Pool, id_account: u64) -> Result<Account, ActixError> {
+    let mut conn = pool.get_conn()?;
+    let mut tx = conn.start_transaction(mysql::TxOpts::default())?;
+
+    let account = select_deleted_account(&mut tx, id_account)?.ok_or(ActixError::NotFound)?;
+    if select_account_by_username(&mut tx, &account.username)?.is_some() {
+        return Err(ActixError::SameAccountName);
+    }
+
+    restore_account(&mut tx, id_account).map_err(duplicate_username)?;
+    tx.commit()?;
+
+    Ok(account)
+}
+
+// `idx_accounts_active_username` keeps usernames unique among accounts that are not deleted,
+// including between instances whose caches do not know about each other's writes yet.
+fn duplicate_username(err: mysql::Error) -> ActixError {
+    match err {
+        mysql::Error::MySqlError(ref mysql_err) if mysql_err.code == ER_DUP_ENTRY => ActixError::SameAccountName,
+        err => ActixError::MysqlError(err),
+    }
+}
+
+pub fn purge_deleted_accounts(pool: &mysql::Pool, retention_days: u32) -> Result<u64, ActixError> {
+    let mut conn = pool.get_conn()?;
+    let mut tx = conn.start_transaction(mysql::TxOpts::default())?;
+
+    let expired = params! {
+        "retention_days" => retention_days,
+    };
+    // Attempts are keyed by username, which an active account may have taken again since.
+    tx.exec_drop(
+        "DELETE FROM login_attempts WHERE attempt_key IN
+         (SELECT CONCAT('account:', purged.username) FROM accounts purged
+          WHERE purged.deleted_at < NOW() - INTERVAL :retention_days DAY
+          AND NOT EXISTS (SELECT 1 FROM accounts active WHERE active.username = purged.username AND active.deleted_at IS NULL))",
+        expired.clone(),
+    )?;
+    tx.exec_drop(
+        "DELETE FROM access_token WHERE id_account IN
+         (SELECT id FROM accounts WHERE deleted_at < NOW() - INTERVAL :retention_days DAY)",
+        expired.clone(),
+    )?;
+    for table in ["account_roles", "password_reset", "account_totp", "recovery_codes"] {
+        tx.exec_drop(
+            format!(
+                "DELETE FROM {} WHERE id_account IN
+                 (SELECT id FROM accounts WHERE deleted_at < NOW() - INTERVAL :retention_days DAY)",
+                table,
+            ),
+            expired.clone(),
+        )?;
+    }
+    tx.exec_drop(
+        "DELETE FROM accounts WHERE deleted_at < NOW() - INTERVAL :retention_days DAY",
+        expired,
+    )?;
+    let purged = tx.affected_rows();
+    tx.commit()?;
+
+    Ok(purged)
+}
+
+// MySQL only counts rows it actually changed as affected, so the callers read the account back,
+// in the same transaction, to tell an unchanged value from a deleted account.
+fn update_account_column(
+    conn: &mut impl Queryable,
+    account_id: u64,
+    column: &str,
+    value: &str,
+) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        format!("UPDATE accounts SET {} = :value WHERE id = :id AND deleted_at IS NULL", column),
+        params! {
+            "id" => account_id,
+            "value" => value,
+        },
+    )
+}
+
+fn soft_delete_account(
+    conn: &mut mysql::PooledConn,
+    account_id: u64,
+) -> mysql::error::Result<u64> {
+    conn.exec_drop(
+        "UPDATE accounts SET deleted_at = NOW() WHERE id = :id AND deleted_at IS NULL",
+        params! {
+            "id" => account_id,
+        },
+    )
+    .map(|_| conn.affected_rows())
+}
+
+fn restore_account(
+    conn: &mut impl Queryable,
+    account_id: u64,
+) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        "UPDATE accounts SET deleted_at = NULL WHERE id = :id AND deleted_at IS NOT NULL",
+        params! {
+            "id" => account_id,
+        },
+    )
+}
+
+fn select_deleted_account(
+    conn: &mut impl Queryable,
+    account_id: u64,
+) -> mysql::error::Result<Option<Account>> {
+    conn.exec_first(
+        "SELECT id, username, password FROM accounts WHERE id = :id AND deleted_at IS NOT NULL",
+        params! {
+            "id" => account_id,
+        },
+    )
+    .map(|row| row.map(|(id, username, password)| Account {
+        id,
+        username,
+        password,
+    }))
+}
+
+fn select_account_by_id(
+    conn: &mut impl Queryable,
+    account_id: u64,
+) -> mysql::error::Result<Option<Account>> {
+    conn.exec_first(
+        "SELECT id, username, password FROM accounts WHERE id = :id AND deleted_at IS NULL",
+        params! {
+            "id" => account_id,
+        },
+    )
+    .map(|row| row.map(|(id, username, password)| Account {
+        id,
+        username,
+        password,
+    }))
+}
+
+fn select_account_by_username(
+    conn: &mut impl Queryable,
+    username: &str,
+) -> mysql::error::Result<Option<Account>> {
+    conn.exec_first(
+        "SELECT id, username, password FROM accounts WHERE username = :username AND deleted_at IS NULL",
+        params! {
+            "username" => username,
+        },
+    )
+    .map(|row| row.map(|(id, username, password)| Account {
+        id,
+        username,
+        password,
+    }))
+}
+
+fn select_database_time(conn: &mut mysql::PooledConn) -> mysql::error::Result<i64> {
+    conn.query_first("SELECT UNIX_TIMESTAMP()").map(Option::unwrap_or_default)
+}
+
+fn select_account_changes(
+    conn: &mut mysql::PooledConn,
+    since: i64,
+) -> mysql::error::Result<Vec<(Account, bool)>> {
+    conn.exec_map(
+        "SELECT id, username, password, deleted_at IS NOT NULL FROM accounts WHERE updated_at >= FROM_UNIXTIME(:since)",
+        params! {
+            "since" => since,
+        },
+        |(id, username, password, deleted)| (Account {
+            id,
+            username,
+            password,
+        }, deleted),
+    )
+}
+
+fn select_account_details(
+    conn: &mut mysql::PooledConn,
+    offset: u64,
+    limit: u64,
+) -> mysql::error::Result<Vec<Account>> {
+    conn.exec_map(
+        "SELECT id, username, password FROM accounts WHERE deleted_at IS NULL ORDER BY id ASC LIMIT :limit OFFSET :offset",
+        params! {
+            "limit" => limit,
+            "offset" => offset,
+        },
+        |(id, username, password)| Account {
+            id,
+            username,
+            password,
+        },
+    )
+}
+
+pub fn insert_account_data(
+    tx: &mut mysql::Transaction,
+    username: &str,
+    hash_password: &str,
+) -> mysql::error::Result<u64> {
+    tx.exec_drop(
+        "INSERT INTO accounts (username,password) VALUES (:username,:password)",
+        params! {
+          "username" => username,
+          "password" => hash_password,
+        },
+    )
+    .map(|_| tx.last_insert_id().unwrap_or_default())
+}
\ No newline at end of file
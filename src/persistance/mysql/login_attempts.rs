@@ -0,0 +1,58 @@
+use crate::errors::ActixError;
+use mysql::prelude::Queryable;
+use mysql::params;
+
+use crate::persistance::LoginAttemptRow;
+
+pub fn get_login_attempts(pool: &mysql::Pool, window_seconds: i64) -> Result<Vec<LoginAttemptRow>, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(select_login_attempts(&mut conn, window_seconds)?)
+}
+
+pub fn save_login_attempt(pool: &mysql::Pool, attempt: &LoginAttemptRow) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(upsert_login_attempt(&mut conn, attempt)?)
+}
+
+pub fn delete_login_attempt(pool: &mysql::Pool, attempt_key: &str) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(conn.exec_drop(
+        "DELETE FROM login_attempts WHERE attempt_key = :attempt_key",
+        params! {
+            "attempt_key" => attempt_key,
+        },
+    )?)
+}
+
+fn select_login_attempts(
+    conn: &mut mysql::PooledConn,
+    window_seconds: i64,
+) -> mysql::error::Result<Vec<LoginAttemptRow>> {
+    conn.exec(
+        "SELECT attempt_key, failures, UNIX_TIMESTAMP(blocked_until), UNIX_TIMESTAMP(last_failure) FROM login_attempts
+         WHERE blocked_until > NOW() OR last_failure > NOW() - INTERVAL :window_seconds SECOND",
+        params! {
+            "window_seconds" => window_seconds,
+        },
+    )
+}
+
+fn upsert_login_attempt(
+    conn: &mut mysql::PooledConn,
+    (attempt_key, failures, blocked_until, last_failure): &LoginAttemptRow,
+) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        "INSERT INTO login_attempts (attempt_key,failures,blocked_until,last_failure)
+         VALUES (:attempt_key,:failures,FROM_UNIXTIME(:blocked_until),FROM_UNIXTIME(:last_failure))
+         ON DUPLICATE KEY UPDATE failures = :failures, blocked_until = FROM_UNIXTIME(:blocked_until), last_failure = FROM_UNIXTIME(:last_failure)",
+        params! {
+            "attempt_key" => attempt_key,
+            "failures" => failures,
+            "blocked_until" => blocked_until,
+            "last_failure" => last_failure,
+        },
+    )
+}
@@ -0,0 +1,117 @@
+use crate::errors::ActixError;
+use crate::migrations::{Dialect, Migration};
+use mysql::prelude::Queryable;
+use mysql::params;
+
+const LOCK_NAME: &str = "nyu_model_migrations";
+
+// `GET_LOCK` belongs to the session, so the connection taking it is kept aside until released.
+pub fn lock_migrations(pool: &mysql::Pool) -> Result<mysql::PooledConn, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    let locked: Option<i64> = conn.exec_first("SELECT GET_LOCK(:name, -1)", params! { "name" => LOCK_NAME })?;
+    match locked {
+        Some(1) => Ok(conn),
+        _ => Err(ActixError::Unknown),
+    }
+}
+
+pub fn unlock_migrations(conn: &mut mysql::PooledConn) -> Result<(), ActixError> {
+    Ok(conn.exec_drop("SELECT RELEASE_LOCK(:name)", params! { "name" => LOCK_NAME })?)
+}
+
+pub fn create_migrations_table(pool: &mysql::Pool) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(conn.query_drop(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INT UNSIGNED PRIMARY KEY,
+            name VARCHAR(255) NOT NULL,
+            checksum CHAR(64) NOT NULL,
+            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        ) CHARSET=utf8mb4",
+    )?)
+}
+
+pub fn has_table(pool: &mysql::Pool, table: &str) -> Result<bool, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    let tables: Option<i64> = conn.exec_first(
+        "SELECT COUNT(*) FROM information_schema.tables WHERE table_schema = DATABASE() AND table_name = :table",
+        params! { "table" => table },
+    )?;
+    Ok(tables.unwrap_or(0) > 0)
+}
+
+// (version, checksum) of every applied migration, by version.
+pub fn get_applied_migrations(pool: &mysql::Pool) -> Result<Vec<(u32, String)>, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(conn.query("SELECT version, checksum FROM schema_migrations ORDER BY version")?)
+}
+
+// MySQL commits each schema change on its own, so a migration failing halfway is left partly
+// applied, and not recorded.
+pub fn apply_migration(pool: &mysql::Pool, migration: &Migration) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    for statement in statements(migration.script(Dialect::Mysql).up) {
+        conn.query_drop(statement)?;
+    }
+    Ok(insert_migration(&mut conn, migration)?)
+}
+
+pub fn revert_migration(pool: &mysql::Pool, migration: &Migration) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    for statement in statements(migration.script(Dialect::Mysql).down) {
+        conn.query_drop(statement)?;
+    }
+    Ok(conn.exec_drop(
+        "DELETE FROM schema_migrations WHERE version = :version",
+        params! {
+            "version" => migration.version,
+        },
+    )?)
+}
+
+// Records the migration as applied without running it, for schemas migrated by hand.
+pub fn mark_migration_applied(pool: &mysql::Pool, migration: &Migration) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(insert_migration(&mut conn, migration)?)
+}
+
+// MySQL scripts hold no `;` but the ones ending their statements.
+fn statements(sql: &str) -> impl Iterator<Item = &str> {
+    sql.split(';').map(str::trim).filter(|statement| !statement.is_empty())
+}
+
+fn insert_migration(conn: &mut mysql::PooledConn, migration: &Migration) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        "INSERT INTO schema_migrations (version,name,checksum) VALUES (:version,:name,:checksum)",
+        params! {
+            "version" => migration.version,
+            "name" => migration.name,
+            "checksum" => migration.checksum(Dialect::Mysql),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::MIGRATIONS;
+
+    #[test]
+    fn scripts_are_split_on_semicolons() {
+        for migration in MIGRATIONS {
+            assert!(statements(migration.mysql.up).count() > 0);
+            assert!(statements(migration.mysql.down).count() > 0);
+        }
+
+        let roles = MIGRATIONS.iter().find(|migration| migration.name == "0006_roles").unwrap();
+        assert_eq!(statements(roles.mysql.up).count(), 9);
+        assert!(statements(roles.mysql.up).all(|statement| !statement.ends_with(';')));
+    }
+}
@@ -0,0 +1,242 @@
+use std::sync::Mutex;
+use crate::accounts::{AccountChange, AccountChangeFeed, AccountStore};
+use crate::errors::ActixError;
+use crate::migrations::{Dialect, Migration};
+use crate::models::{AccessToken, Account, AccountTotp};
+use super::{Database, LoginAttemptRow, RefreshTokenRow};
+
+mod access;
+mod accounts;
+mod login_attempts;
+mod migrations;
+mod password_reset;
+mod roles;
+mod two_factor;
+
+pub struct MysqlDatabase {
+    pool: mysql::Pool,
+    // Connection holding the migration lock, while it is held.
+    migration_lock: Mutex<Option<mysql::PooledConn>>,
+}
+
+impl MysqlDatabase {
+    pub fn new(pool: mysql::Pool) -> MysqlDatabase {
+        MysqlDatabase { pool, migration_lock: Mutex::new(None) }
+    }
+
+    pub fn connect(url: &str) -> Result<MysqlDatabase, String> {
+        mysql::Pool::new(url).map(MysqlDatabase::new).map_err(|err| err.to_string())
+    }
+}
+
+impl AccountStore for MysqlDatabase {
+    fn create(&self, username: &str, hash_password: &str) -> Result<Account, ActixError> {
+        accounts::create_new_account(&self.pool, username, hash_password)
+    }
+
+    fn get(&self, id_account: u64) -> Result<Account, ActixError> {
+        accounts::get_account_by_id_data(&self.pool, id_account)
+    }
+
+    fn get_by_username(&self, username: &str) -> Result<Account, ActixError> {
+        accounts::get_account_by_username_data(&self.pool, username)
+    }
+
+    fn list(&self, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError> {
+        accounts::get_account_data(&self.pool, offset, limit)
+    }
+
+    fn update_username(&self, id_account: u64, username: &str) -> Result<Account, ActixError> {
+        accounts::update_account_username_data(&self.pool, id_account, username)
+    }
+
+    fn update_password(&self, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+        accounts::update_account_password_data(&self.pool, id_account, hash_password)
+    }
+
+    fn delete(&self, id_account: u64) -> Result<(), ActixError> {
+        accounts::delete_account_data(&self.pool, id_account)
+    }
+
+    fn restore(&self, id_account: u64) -> Result<Account, ActixError> {
+        accounts::restore_account_data(&self.pool, id_account)
+    }
+}
+
+impl AccountChangeFeed for MysqlDatabase {
+    fn now(&self) -> Result<i64, ActixError> {
+        accounts::get_database_time(&self.pool)
+    }
+
+    fn changes_since(&self, since: i64) -> Result<(Vec<AccountChange>, i64), ActixError> {
+        let (rows, now) = accounts::get_account_changes(&self.pool, since)?;
+        let changes = rows.into_iter()
+            .map(|(account, deleted)| if deleted {
+                AccountChange::Deleted(account.id)
+            } else {
+                AccountChange::Saved(account)
+            })
+            .collect();
+
+        Ok((changes, now))
+    }
+}
+
+impl Database for MysqlDatabase {
+    fn get_account_authorizations(&self, id_account: u64) -> Result<(Vec<String>, Vec<String>), ActixError> {
+        roles::get_account_authorizations(&mut self.pool.get_conn()?, id_account)
+    }
+
+    fn insert_access_token(&self, id_account: u64, access_token: &str, refresh_token: &str, family: &str) -> Result<AccessToken, ActixError> {
+        access::insert_access_token_data(&self.pool, id_account, access_token, refresh_token, family)
+    }
+
+    fn get_refresh_token(&self, refresh_token: &str) -> Result<Option<RefreshTokenRow>, ActixError> {
+        access::get_refresh_token(&self.pool, refresh_token)
+    }
+
+    fn revoke_token_pair(&self, id: u64) -> Result<bool, ActixError> {
+        access::revoke_token_pair(&self.pool, id)
+    }
+
+    fn revoke_token_family(&self, family: &str) -> Result<(), ActixError> {
+        access::revoke_token_family_data(&self.pool, family)
+    }
+
+    fn revoke_access_token(&self, access_token: &str) -> Result<(), ActixError> {
+        access::revoke_access_token_data(&self.pool, access_token)
+    }
+
+    fn revoke_account_tokens(&self, id_account: u64) -> Result<Vec<(String, i64)>, ActixError> {
+        access::revoke_account_tokens(&self.pool, id_account)
+    }
+
+    fn get_revoked_access_tokens(&self) -> Result<Vec<(String, i64)>, ActixError> {
+        access::get_revoked_access_tokens(&self.pool)
+    }
+
+    fn get_totp(&self, id_account: u64) -> Result<Option<AccountTotp>, ActixError> {
+        two_factor::get_totp_data(&self.pool, id_account)
+    }
+
+    fn save_pending_totp(&self, id_account: u64, secret: &str) -> Result<(), ActixError> {
+        two_factor::save_pending_totp(&self.pool, id_account, secret)
+    }
+
+    fn enable_totp(&self, id_account: u64, recovery_codes: &[String]) -> Result<(), ActixError> {
+        two_factor::enable_totp(&self.pool, id_account, recovery_codes)
+    }
+
+    fn disable_totp(&self, id_account: u64) -> Result<(), ActixError> {
+        two_factor::disable_totp(&self.pool, id_account)
+    }
+
+    fn verify_second_factor(&self, account_totp: &AccountTotp, code: &str) -> Result<(), ActixError> {
+        two_factor::verify_second_factor(&self.pool, account_totp, code)
+    }
+
+    fn create_password_reset(&self, id_account: u64, token_hash: &str) -> Result<(), ActixError> {
+        password_reset::create_password_reset(&self.pool, id_account, token_hash)
+    }
+
+    fn get_password_reset(&self, token_hash: &str) -> Result<u64, ActixError> {
+        password_reset::get_password_reset(&self.pool, token_hash)
+    }
+
+    fn reset_password(&self, token_hash: &str, hash_password: &str) -> Result<Account, ActixError> {
+        password_reset::reset_password(&self.pool, token_hash, hash_password)
+    }
+
+    fn get_login_attempts(&self, window_seconds: i64) -> Result<Vec<LoginAttemptRow>, ActixError> {
+        login_attempts::get_login_attempts(&self.pool, window_seconds)
+    }
+
+    fn save_login_attempt(&self, attempt: &LoginAttemptRow) -> Result<(), ActixError> {
+        login_attempts::save_login_attempt(&self.pool, attempt)
+    }
+
+    fn delete_login_attempt(&self, attempt_key: &str) -> Result<(), ActixError> {
+        login_attempts::delete_login_attempt(&self.pool, attempt_key)
+    }
+
+    fn purge_deleted_accounts(&self, retention_days: u32) -> Result<u64, ActixError> {
+        accounts::purge_deleted_accounts(&self.pool, retention_days)
+    }
+
+    fn dialect(&self) -> Dialect {
+        Dialect::Mysql
+    }
+
+    fn lock_migrations(&self) -> Result<(), ActixError> {
+        let conn = migrations::lock_migrations(&self.pool)?;
+        *self.migration_lock.lock().unwrap() = Some(conn);
+        Ok(())
+    }
+
+    fn unlock_migrations(&self) -> Result<(), ActixError> {
+        match self.migration_lock.lock().unwrap().take() {
+            Some(mut conn) => migrations::unlock_migrations(&mut conn),
+            None => Ok(()),
+        }
+    }
+
+    fn create_migrations_table(&self) -> Result<(), ActixError> {
+        migrations::create_migrations_table(&self.pool)
+    }
+
+    fn has_table(&self, table: &str) -> Result<bool, ActixError> {
+        migrations::has_table(&self.pool, table)
+    }
+
+    fn get_applied_migrations(&self) -> Result<Vec<(u32, String)>, ActixError> {
+        migrations::get_applied_migrations(&self.pool)
+    }
+
+    fn apply_migration(&self, migration: &Migration) -> Result<(), ActixError> {
+        migrations::apply_migration(&self.pool, migration)
+    }
+
+    fn revert_migration(&self, migration: &Migration) -> Result<(), ActixError> {
+        migrations::revert_migration(&self.pool, migration)
+    }
+
+    fn mark_migration_applied(&self, migration: &Migration) -> Result<(), ActixError> {
+        migrations::mark_migration_applied(&self.pool, migration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use mysql::prelude::Queryable;
+    use crate::migrations::{run, MigrateArgs, MigrateCommand, MIGRATIONS};
+    use crate::persistance::suite;
+
+    fn migrate(db: &MysqlDatabase, command: MigrateCommand) {
+        run(db, &MigrateArgs { command, dry_run: false }).unwrap();
+    }
+
+    // Runs the shared cases on an empty database, which it leaves empty again:
+    // `MYSQL_URL=mysql://root@localhost/nyu_model_test cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn behaves_like_the_other_backends() {
+        let url = env::var("MYSQL_URL").expect("MYSQL_URL must be set");
+        let db = MysqlDatabase::connect(&url).unwrap();
+
+        for case in suite::CASES {
+            migrate(&db, MigrateCommand::Up);
+            case(&db);
+            migrate(&db, MigrateCommand::Down(MIGRATIONS.len()));
+        }
+
+        migrate(&db, MigrateCommand::Up);
+        suite::purged_accounts_take_their_login_attempts_along(&db, || {
+            db.pool.get_conn().unwrap()
+                .query_drop("UPDATE accounts SET deleted_at = NOW() - INTERVAL 40 DAY WHERE deleted_at IS NOT NULL")
+                .unwrap();
+        });
+        migrate(&db, MigrateCommand::Down(MIGRATIONS.len()));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::models::Account;
+use crate::errors::ActixError;
+use crate::persistance::PASSWORD_RESET_LIFETIME_MINUTES;
+use super::accounts::update_account_password;
+use mysql::prelude::Queryable;
+use mysql::params;
+
+pub fn create_password_reset(pool: &mysql::Pool, id_account: u64, token_hash: &str) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(insert_password_reset(&mut conn, id_account, token_hash)?)
+}
+
+pub fn get_password_reset(pool: &mysql::Pool, token_hash: &str) -> Result<u64, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    select_valid_password_reset(&mut conn, token_hash)?.ok_or(ActixError::UnAuthorized)
+}
+
+// Marks the token as used and stores the new password of its account; every other pending token
+// of the account is burnt too.
+pub fn reset_password(pool: &mysql::Pool, token_hash: &str, hash_password: &str) -> Result<Account, ActixError> {
+    let mut conn = pool.get_conn()?;
+    let mut tx = conn.start_transaction(mysql::TxOpts::default())?;
+
+    let id_account = select_valid_password_reset(&mut tx, token_hash)?
+        .ok_or(ActixError::UnAuthorized)?;
+    invalidate_password_resets(&mut tx, id_account)?;
+    let account = update_account_password(&mut tx, id_account, hash_password)?;
+    tx.commit()?;
+
+    Ok(account)
+}
+
+fn insert_password_reset(
+    conn: &mut mysql::PooledConn,
+    id_account: u64,
+    token_hash: &str,
+) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        "INSERT INTO password_reset (id_account,token_hash,expires_at) VALUES (:id_account,:token_hash,NOW() + INTERVAL :minutes MINUTE)",
+        params! {
+            "id_account" => id_account,
+            "token_hash" => token_hash,
+            "minutes" => PASSWORD_RESET_LIFETIME_MINUTES,
+        },
+    )
+}
+
+fn select_valid_password_reset(
+    conn: &mut impl Queryable,
+    token_hash: &str,
+) -> mysql::error::Result<Option<u64>> {
+    conn.exec_first(
+        "SELECT id_account FROM password_reset
+         WHERE token_hash = :token_hash AND used_at IS NULL AND expires_at > NOW() FOR UPDATE",
+        params! {
+            "token_hash" => token_hash,
+        },
+    )
+}
+
+fn invalidate_password_resets(
+    conn: &mut impl Queryable,
+    id_account: u64,
+) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        "UPDATE password_reset SET used_at = NOW() WHERE id_account = :id_account AND used_at IS NULL",
+        params! {
+            "id_account" => id_account,
+        },
+    )
+}
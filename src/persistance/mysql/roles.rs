@@ -0,0 +1,56 @@
+use crate::errors::ActixError;
+use mysql::prelude::Queryable;
+use mysql::params;
+
+pub fn get_account_authorizations(
+    conn: &mut impl Queryable,
+    id_account: u64,
+) -> Result<(Vec<String>, Vec<String>), ActixError> {
+    let roles = select_account_roles(conn, id_account)?;
+    let permissions = select_account_permissions(conn, id_account)?;
+
+    Ok((roles, permissions))
+}
+
+pub fn insert_account_role(
+    conn: &mut impl Queryable,
+    id_account: u64,
+    role: &str,
+) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        "INSERT INTO account_roles (id_account,id_role) SELECT :id_account, id FROM roles WHERE name = :role",
+        params! {
+            "id_account" => id_account,
+            "role" => role,
+        },
+    )
+}
+
+fn select_account_roles(
+    conn: &mut impl Queryable,
+    id_account: u64,
+) -> mysql::error::Result<Vec<String>> {
+    conn.exec(
+        "SELECT r.name FROM roles r
+         JOIN account_roles ar ON ar.id_role = r.id
+         WHERE ar.id_account = :id_account ORDER BY r.name ASC",
+        params! {
+            "id_account" => id_account,
+        },
+    )
+}
+
+fn select_account_permissions(
+    conn: &mut impl Queryable,
+    id_account: u64,
+) -> mysql::error::Result<Vec<String>> {
+    conn.exec(
+        "SELECT DISTINCT p.name FROM permissions p
+         JOIN role_permissions rp ON rp.id_permission = p.id
+         JOIN account_roles ar ON ar.id_role = rp.id_role
+         WHERE ar.id_account = :id_account ORDER BY p.name ASC",
+        params! {
+            "id_account" => id_account,
+        },
+    )
+}
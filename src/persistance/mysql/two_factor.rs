@@ -0,0 +1,148 @@
+use crate::errors::ActixError;
+use crate::models::AccountTotp;
+use crate::password::hash_token;
+use crate::persistance::{second_factor, SecondFactor};
+use mysql::prelude::Queryable;
+use mysql::params;
+
+pub fn get_totp_data(pool: &mysql::Pool, id_account: u64) -> Result<Option<AccountTotp>, ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(select_account_totp(&mut conn, id_account)?)
+}
+
+pub fn save_pending_totp(pool: &mysql::Pool, id_account: u64, secret: &str) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    Ok(upsert_account_totp(&mut conn, id_account, secret)?)
+}
+
+pub fn enable_totp(pool: &mysql::Pool, id_account: u64, recovery_codes: &[String]) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+    let mut tx = conn.start_transaction(mysql::TxOpts::default())?;
+
+    tx.exec_drop(
+        "UPDATE account_totp SET enabled = 1 WHERE id_account = :id_account",
+        params! {
+            "id_account" => id_account,
+        },
+    )?;
+    delete_recovery_codes(&mut tx, id_account)?;
+    tx.exec_batch(
+        "INSERT INTO recovery_codes (id_account,code_hash) VALUES (:id_account,:code_hash)",
+        recovery_codes.iter().map(|code| params! {
+            "id_account" => id_account,
+            "code_hash" => hash_token(code),
+        }),
+    )?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+pub fn disable_totp(pool: &mysql::Pool, id_account: u64) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+    let mut tx = conn.start_transaction(mysql::TxOpts::default())?;
+
+    tx.exec_drop(
+        "DELETE FROM account_totp WHERE id_account = :id_account",
+        params! {
+            "id_account" => id_account,
+        },
+    )?;
+    delete_recovery_codes(&mut tx, id_account)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+// Accepts either a TOTP code or an unused recovery code. Both are burnt once accepted.
+pub fn verify_second_factor(pool: &mysql::Pool, account_totp: &AccountTotp, code: &str) -> Result<(), ActixError> {
+    let mut conn = pool.get_conn()?;
+
+    let accepted = match second_factor(account_totp, code) {
+        SecondFactor::TotpStep(step) => use_totp_step(&mut conn, account_totp.id_account, step)? > 0,
+        SecondFactor::RecoveryCode(code_hash) => use_recovery_code(&mut conn, account_totp.id_account, &code_hash)? > 0,
+        SecondFactor::Invalid => false,
+    };
+
+    if accepted {
+        Ok(())
+    } else {
+        Err(ActixError::UnAuthorized)
+    }
+}
+
+fn select_account_totp(
+    conn: &mut mysql::PooledConn,
+    id_account: u64,
+) -> mysql::error::Result<Option<AccountTotp>> {
+    conn.exec_first(
+        "SELECT id_account, secret, enabled FROM account_totp WHERE id_account = :id_account",
+        params! {
+            "id_account" => id_account,
+        },
+    )
+    .map(|row| row.map(|(id_account, secret, enabled)| AccountTotp {
+        id_account,
+        secret,
+        enabled,
+    }))
+}
+
+fn upsert_account_totp(
+    conn: &mut mysql::PooledConn,
+    id_account: u64,
+    secret: &str,
+) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        "INSERT INTO account_totp (id_account,secret) VALUES (:id_account,:secret)
+         ON DUPLICATE KEY UPDATE secret = :secret, enabled = 0, last_used_step = 0",
+        params! {
+            "id_account" => id_account,
+            "secret" => secret,
+        },
+    )
+}
+
+fn use_totp_step(
+    conn: &mut mysql::PooledConn,
+    id_account: u64,
+    step: u64,
+) -> mysql::error::Result<u64> {
+    conn.exec_drop(
+        "UPDATE account_totp SET last_used_step = :step WHERE id_account = :id_account AND last_used_step < :step",
+        params! {
+            "id_account" => id_account,
+            "step" => step,
+        },
+    )
+    .map(|_| conn.affected_rows())
+}
+
+fn use_recovery_code(
+    conn: &mut mysql::PooledConn,
+    id_account: u64,
+    code_hash: &str,
+) -> mysql::error::Result<u64> {
+    conn.exec_drop(
+        "UPDATE recovery_codes SET used_at = NOW() WHERE id_account = :id_account AND code_hash = :code_hash AND used_at IS NULL",
+        params! {
+            "id_account" => id_account,
+            "code_hash" => code_hash,
+        },
+    )
+    .map(|_| conn.affected_rows())
+}
+
+fn delete_recovery_codes(
+    conn: &mut impl Queryable,
+    id_account: u64,
+) -> mysql::error::Result<()> {
+    conn.exec_drop(
+        "DELETE FROM recovery_codes WHERE id_account = :id_account",
+        params! {
+            "id_account" => id_account,
+        },
+    )
+}
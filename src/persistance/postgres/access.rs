@@ -0,0 +1,94 @@
+use crate::models::AccessToken;
+use crate::persistance::RefreshTokenRow;
+use crate::tokens::ACCESS_TOKEN_LIFETIME_MINUTES;
+use crate::errors::ActixError;
+use postgres::GenericClient;
+
+pub fn insert_access_token_data(
+    conn: &mut impl GenericClient,
+    id_account: u64,
+    access_token: &str,
+    refresh_token: &str,
+    family: &str,
+) -> Result<AccessToken, ActixError> {
+    let row = conn.query_one(
+        "INSERT INTO access_token (id_account,access_token,refresh_token,family) VALUES ($1,$2,$3,$4) RETURNING id",
+        &[&(id_account as i64), &access_token, &refresh_token, &family],
+    )?;
+
+    Ok(AccessToken{
+        id: row.get::<_, i64>(0) as u64,
+        access_token: access_token.to_string(),
+        id_account,
+        refresh_token: refresh_token.to_string(),
+    })
+}
+
+pub fn get_refresh_token(conn: &mut impl GenericClient, refresh_token: &str) -> Result<Option<RefreshTokenRow>, ActixError> {
+    let row = conn.query_opt(
+        "SELECT id, id_account, family, revoked_at IS NOT NULL FROM access_token WHERE refresh_token = $1",
+        &[&refresh_token],
+    )?;
+
+    Ok(row.map(|row| (row.get::<_, i64>(0) as u64, row.get::<_, i64>(1) as u64, row.get(2), row.get(3))))
+}
+
+pub fn revoke_token_pair(conn: &mut impl GenericClient, id: u64) -> Result<bool, ActixError> {
+    let revoked = conn.execute(
+        "UPDATE access_token SET revoked_at = CURRENT_TIMESTAMP WHERE id = $1 AND revoked_at IS NULL",
+        &[&(id as i64)],
+    )?;
+
+    Ok(revoked > 0)
+}
+
+pub fn revoke_token_family(conn: &mut impl GenericClient, family: &str) -> Result<(), ActixError> {
+    conn.execute(
+        "UPDATE access_token SET revoked_at = CURRENT_TIMESTAMP WHERE family = $1 AND revoked_at IS NULL",
+        &[&family],
+    )?;
+
+    Ok(())
+}
+
+pub fn revoke_access_token_data(conn: &mut impl GenericClient, access_token: &str) -> Result<(), ActixError> {
+    conn.execute(
+        "UPDATE access_token SET revoked_at = CURRENT_TIMESTAMP WHERE access_token = $1 AND revoked_at IS NULL",
+        &[&access_token],
+    )?;
+
+    Ok(())
+}
+
+pub fn revoke_account_tokens(conn: &mut impl GenericClient, id_account: u64) -> Result<Vec<(String, i64)>, ActixError> {
+    let mut tx = conn.transaction()?;
+
+    let active_tokens = select_active_access_tokens(&mut tx, id_account)?;
+    tx.execute(
+        "UPDATE access_token SET revoked_at = CURRENT_TIMESTAMP WHERE id_account = $1 AND revoked_at IS NULL",
+        &[&(id_account as i64)],
+    )?;
+    tx.commit()?;
+
+    Ok(active_tokens)
+}
+
+pub fn get_revoked_access_tokens(conn: &mut impl GenericClient) -> Result<Vec<(String, i64)>, ActixError> {
+    let rows = conn.query(
+        "SELECT access_token, EXTRACT(EPOCH FROM date_added + $1::BIGINT * INTERVAL '1 minute')::BIGINT FROM access_token
+         WHERE revoked_at IS NOT NULL AND date_added > CURRENT_TIMESTAMP - $1::BIGINT * INTERVAL '1 minute'",
+        &[&ACCESS_TOKEN_LIFETIME_MINUTES],
+    )?;
+
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+fn select_active_access_tokens(conn: &mut impl GenericClient, id_account: u64) -> Result<Vec<(String, i64)>, postgres::Error> {
+    let rows = conn.query(
+        "SELECT access_token, EXTRACT(EPOCH FROM date_added + $2::BIGINT * INTERVAL '1 minute')::BIGINT FROM access_token
+         WHERE id_account = $1 AND revoked_at IS NULL AND date_added > CURRENT_TIMESTAMP - $2::BIGINT * INTERVAL '1 minute'",
+        &[&(id_account as i64), &ACCESS_TOKEN_LIFETIME_MINUTES],
+    )?;
+
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
@@ -0,0 +1,183 @@
+use crate::models::Account;
+use crate::errors::ActixError;
+use crate::persistance::DEFAULT_ROLE;
+use super::roles::insert_account_role;
+use postgres::error::SqlState;
+use postgres::{GenericClient, Row};
+
+pub fn create_new_account(
+    conn: &mut impl GenericClient,
+    username: &str,
+    hash_password: &str,
+) -> Result<Account, ActixError> {
+    let mut tx = conn.transaction()?;
+
+    let row = tx.query_one(
+        "INSERT INTO accounts (username,password) VALUES ($1,$2) RETURNING id",
+        &[&username, &hash_password],
+    )
+    .map_err(duplicate_username)?;
+    let id = row.get::<_, i64>(0) as u64;
+    insert_account_role(&mut tx, id, DEFAULT_ROLE)?;
+    tx.commit()?;
+
+    Ok(Account{
+        id,
+        password: String::from(hash_password),
+        username: String::from(username),
+    })
+}
+
+pub fn get_account_data(conn: &mut impl GenericClient, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError> {
+    let rows = conn.query(
+        "SELECT id, username, password FROM accounts WHERE deleted_at IS NULL ORDER BY id ASC LIMIT $1 OFFSET $2",
+        &[&i64::try_from(limit).unwrap_or(i64::MAX), &i64::try_from(offset).unwrap_or(i64::MAX)],
+    )?;
+
+    Ok(rows.iter().map(account_from_row).collect())
+}
+
+pub fn get_account_by_id_data(conn: &mut impl GenericClient, id_account: u64) -> Result<Account, ActixError> {
+    select_account_by_id(conn, id_account)?.ok_or(ActixError::NotFound)
+}
+
+pub fn get_account_by_username_data(conn: &mut impl GenericClient, username: &str) -> Result<Account, ActixError> {
+    select_account_by_username(conn, username)?.ok_or(ActixError::NotFound)
+}
+
+pub fn update_account_username_data(conn: &mut impl GenericClient, id_account: u64, username: &str) -> Result<Account, ActixError> {
+    update_account_column(conn, id_account, "username", username)
+        .map_err(duplicate_username)?
+        .ok_or(ActixError::NotFound)
+}
+
+pub fn update_account_password_data(conn: &mut impl GenericClient, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+    update_account_column(conn, id_account, "password", hash_password)?.ok_or(ActixError::NotFound)
+}
+
+pub fn delete_account_data(conn: &mut impl GenericClient, id_account: u64) -> Result<(), ActixError> {
+    let deleted = conn.execute(
+        "UPDATE accounts SET deleted_at = CURRENT_TIMESTAMP WHERE id = $1 AND deleted_at IS NULL",
+        &[&(id_account as i64)],
+    )?;
+
+    match deleted {
+        0 => Err(ActixError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+pub fn get_database_time(conn: &mut impl GenericClient) -> Result<i64, ActixError> {
+    Ok(conn.query_one("SELECT FLOOR(EXTRACT(EPOCH FROM CURRENT_TIMESTAMP))::BIGINT", &[])?.get(0))
+}
+
+// Accounts whose row changed at or after `since` (seconds since epoch) with whether they are
+// deleted, and the database time the lookup was made at.
+pub fn get_account_changes(conn: &mut impl GenericClient, since: i64) -> Result<(Vec<(Account, bool)>, i64), ActixError> {
+    let now = get_database_time(conn)?;
+
+    let rows = conn.query(
+        "SELECT id, username, password, deleted_at IS NOT NULL FROM accounts WHERE updated_at >= to_timestamp($1::BIGINT)",
+        &[&since],
+    )?;
+
+    Ok((rows.iter().map(|row| (account_from_row(row), row.get(3))).collect(), now))
+}
+
+// Refused when another account took the username in the meantime.
+pub fn restore_account_data(conn: &mut impl GenericClient, id_account: u64) -> Result<Account, ActixError> {
+    let mut tx = conn.transaction()?;
+
+    let account = tx.query_opt(
+        "SELECT id, username, password FROM accounts WHERE id = $1 AND deleted_at IS NOT NULL",
+        &[&(id_account as i64)],
+    )?
+    .map(|row| account_from_row(&row))
+    .ok_or(ActixError::NotFound)?;
+    if select_account_by_username(&mut tx, &account.username)?.is_some() {
+        return Err(ActixError::SameAccountName);
+    }
+
+    tx.execute(
+        "UPDATE accounts SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL",
+        &[&(id_account as i64)],
+    )
+    .map_err(duplicate_username)?;
+    tx.commit()?;
+
+    Ok(account)
+}
+
+pub fn purge_deleted_accounts(conn: &mut impl GenericClient, retention_days: u32) -> Result<u64, ActixError> {
+    let mut tx = conn.transaction()?;
+    let retention_days = i64::from(retention_days);
+
+    // Attempts are keyed by username, which an active account may have taken again since.
+    tx.execute(
+        "DELETE FROM login_attempts WHERE attempt_key IN
+         (SELECT 'account:' || purged.username FROM accounts purged
+          WHERE purged.deleted_at < CURRENT_TIMESTAMP - $1::BIGINT * INTERVAL '1 day'
+          AND NOT EXISTS (SELECT 1 FROM accounts active WHERE active.username = purged.username AND active.deleted_at IS NULL))",
+        &[&retention_days],
+    )?;
+    for table in ["access_token", "account_roles", "password_reset", "account_totp", "recovery_codes"] {
+        tx.execute(
+            &format!(
+                "DELETE FROM {} WHERE id_account IN
+                 (SELECT id FROM accounts WHERE deleted_at < CURRENT_TIMESTAMP - $1::BIGINT * INTERVAL '1 day')",
+                table,
+            ),
+            &[&retention_days],
+        )?;
+    }
+    let purged = tx.execute(
+        "DELETE FROM accounts WHERE deleted_at < CURRENT_TIMESTAMP - $1::BIGINT * INTERVAL '1 day'",
+        &[&retention_days],
+    )?;
+    tx.commit()?;
+
+    Ok(purged)
+}
+
+// `idx_accounts_active_username` keeps usernames unique among accounts that are not deleted.
+fn duplicate_username(err: postgres::Error) -> ActixError {
+    match err.code() {
+        Some(code) if *code == SqlState::UNIQUE_VIOLATION => ActixError::SameAccountName,
+        _ => ActixError::PostgresError(err),
+    }
+}
+
+fn update_account_column(conn: &mut impl GenericClient, account_id: u64, column: &str, value: &str) -> Result<Option<Account>, postgres::Error> {
+    let row = conn.query_opt(
+        &format!("UPDATE accounts SET {} = $2 WHERE id = $1 AND deleted_at IS NULL RETURNING id, username, password", column),
+        &[&(account_id as i64), &value],
+    )?;
+
+    Ok(row.as_ref().map(account_from_row))
+}
+
+fn select_account_by_id(conn: &mut impl GenericClient, account_id: u64) -> Result<Option<Account>, postgres::Error> {
+    let row = conn.query_opt(
+        "SELECT id, username, password FROM accounts WHERE id = $1 AND deleted_at IS NULL",
+        &[&(account_id as i64)],
+    )?;
+
+    Ok(row.as_ref().map(account_from_row))
+}
+
+fn select_account_by_username(conn: &mut impl GenericClient, username: &str) -> Result<Option<Account>, postgres::Error> {
+    let row = conn.query_opt(
+        "SELECT id, username, password FROM accounts WHERE username = $1 AND deleted_at IS NULL",
+        &[&username],
+    )?;
+
+    Ok(row.as_ref().map(account_from_row))
+}
+
+fn account_from_row(row: &Row) -> Account {
+    Account {
+        id: row.get::<_, i64>(0) as u64,
+        username: row.get(1),
+        password: row.get(2),
+    }
+}
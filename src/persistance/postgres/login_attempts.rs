@@ -0,0 +1,36 @@
+use crate::errors::ActixError;
+use crate::persistance::LoginAttemptRow;
+use postgres::GenericClient;
+
+pub fn get_login_attempts(conn: &mut impl GenericClient, window_seconds: i64) -> Result<Vec<LoginAttemptRow>, ActixError> {
+    let rows = conn.query(
+        "SELECT attempt_key, failures, EXTRACT(EPOCH FROM blocked_until)::BIGINT, EXTRACT(EPOCH FROM last_failure)::BIGINT FROM login_attempts
+         WHERE blocked_until > CURRENT_TIMESTAMP OR last_failure > CURRENT_TIMESTAMP - $1::BIGINT * INTERVAL '1 second'",
+        &[&window_seconds],
+    )?;
+
+    Ok(rows.iter().map(|row| (row.get(0), row.get::<_, i32>(1) as u32, row.get(2), row.get(3))).collect())
+}
+
+pub fn save_login_attempt(
+    conn: &mut impl GenericClient,
+    (attempt_key, failures, blocked_until, last_failure): &LoginAttemptRow,
+) -> Result<(), ActixError> {
+    conn.execute(
+        "INSERT INTO login_attempts (attempt_key,failures,blocked_until,last_failure)
+         VALUES ($1,$2,to_timestamp($3::BIGINT),to_timestamp($4::BIGINT))
+         ON CONFLICT (attempt_key) DO UPDATE SET failures = $2, blocked_until = to_timestamp($3::BIGINT), last_failure = to_timestamp($4::BIGINT)",
+        &[attempt_key, &(*failures as i32), blocked_until, last_failure],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_login_attempt(conn: &mut impl GenericClient, attempt_key: &str) -> Result<(), ActixError> {
+    conn.execute(
+        "DELETE FROM login_attempts WHERE attempt_key = $1",
+        &[&attempt_key],
+    )?;
+
+    Ok(())
+}
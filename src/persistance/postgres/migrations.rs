@@ -0,0 +1,84 @@
+use crate::errors::ActixError;
+use crate::migrations::{Dialect, Migration};
+use postgres::GenericClient;
+
+// Key of the session-level advisory lock held while migrations run.
+const LOCK_KEY: i64 = 0x6e79_755f_6d69_6772;
+
+pub fn lock_migrations(conn: &mut impl GenericClient) -> Result<(), ActixError> {
+    conn.execute("SELECT pg_advisory_lock($1)", &[&LOCK_KEY])?;
+
+    Ok(())
+}
+
+pub fn unlock_migrations(conn: &mut impl GenericClient) -> Result<(), ActixError> {
+    conn.execute("SELECT pg_advisory_unlock($1)", &[&LOCK_KEY])?;
+
+    Ok(())
+}
+
+pub fn create_migrations_table(conn: &mut impl GenericClient) -> Result<(), ActixError> {
+    conn.batch_execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name VARCHAR(255) NOT NULL,
+            checksum CHAR(64) NOT NULL,
+            applied_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+        )",
+    )?;
+
+    Ok(())
+}
+
+pub fn has_table(conn: &mut impl GenericClient, table: &str) -> Result<bool, ActixError> {
+    let row = conn.query_one(
+        "SELECT EXISTS (SELECT 1 FROM information_schema.tables WHERE table_schema = current_schema() AND table_name = $1)",
+        &[&table],
+    )?;
+
+    Ok(row.get(0))
+}
+
+// (version, checksum) of every applied migration, by version.
+pub fn get_applied_migrations(conn: &mut impl GenericClient) -> Result<Vec<(u32, String)>, ActixError> {
+    let rows = conn.query("SELECT version, checksum FROM schema_migrations ORDER BY version", &[])?;
+
+    Ok(rows.iter().map(|row| (row.get::<_, i32>(0) as u32, row.get(1))).collect())
+}
+
+// Schema changes are transactional in PostgreSQL: a failing migration leaves nothing behind.
+pub fn apply_migration(conn: &mut impl GenericClient, migration: &Migration) -> Result<(), ActixError> {
+    let mut tx = conn.transaction()?;
+
+    tx.batch_execute(migration.script(Dialect::Postgres).up)?;
+    insert_migration(&mut tx, migration)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+pub fn revert_migration(conn: &mut impl GenericClient, migration: &Migration) -> Result<(), ActixError> {
+    let mut tx = conn.transaction()?;
+
+    tx.batch_execute(migration.script(Dialect::Postgres).down)?;
+    tx.execute(
+        "DELETE FROM schema_migrations WHERE version = $1",
+        &[&(migration.version as i32)],
+    )?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+// Records the migration as applied without running it.
+pub fn mark_migration_applied(conn: &mut impl GenericClient, migration: &Migration) -> Result<(), ActixError> {
+    Ok(insert_migration(conn, migration)?)
+}
+
+fn insert_migration(conn: &mut impl GenericClient, migration: &Migration) -> Result<(), postgres::Error> {
+    conn.execute(
+        "INSERT INTO schema_migrations (version,name,checksum) VALUES ($1,$2,$3)",
+        &[&(migration.version as i32), &migration.name, &migration.checksum(Dialect::Postgres)],
+    )
+    .map(|_| ())
+}
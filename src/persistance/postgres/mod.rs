@@ -0,0 +1,272 @@
+use std::sync::Mutex;
+use std::thread;
+use actix_web::rt::System;
+use postgres::{Client, NoTls};
+use crate::accounts::{AccountChange, AccountChangeFeed, AccountStore};
+use crate::errors::ActixError;
+use crate::migrations::{Dialect, Migration};
+use crate::models::{AccessToken, Account, AccountTotp};
+use super::{Database, LoginAttemptRow, RefreshTokenRow};
+
+mod access;
+mod accounts;
+mod login_attempts;
+mod migrations;
+mod password_reset;
+mod roles;
+mod two_factor;
+
+pub struct PostgresDatabase {
+    // Only taken when dropped.
+    client: Mutex<Option<Client>>,
+}
+
+impl PostgresDatabase {
+    pub fn connect(url: &str) -> Result<PostgresDatabase, String> {
+        let client = off_runtime(|| Client::connect(url, NoTls)).map_err(|err| err.to_string())?;
+
+        Ok(PostgresDatabase { client: Mutex::new(Some(client)) })
+    }
+
+    fn with<T: Send>(&self, query: impl FnOnce(&mut Client) -> Result<T, ActixError> + Send) -> Result<T, ActixError> {
+        let mut client = self.client.lock().unwrap();
+        let client = client.as_mut().unwrap();
+
+        off_runtime(|| query(client))
+    }
+}
+
+// Closing the connection blocks as well.
+impl Drop for PostgresDatabase {
+    fn drop(&mut self) {
+        if let Ok(client) = self.client.get_mut() {
+            let client = client.take();
+            off_runtime(|| drop(client));
+        }
+    }
+}
+
+// The client blocks on a runtime of its own, which panics on a thread already driving async
+// tasks (startup code runs on one). Calls made there go through a short-lived thread instead.
+fn off_runtime<T: Send>(call: impl FnOnce() -> T + Send) -> T {
+    if System::try_current().is_none() {
+        return call();
+    }
+
+    thread::scope(|scope| scope.spawn(call).join().unwrap())
+}
+
+impl AccountStore for PostgresDatabase {
+    fn create(&self, username: &str, hash_password: &str) -> Result<Account, ActixError> {
+        self.with(|conn| accounts::create_new_account(conn, username, hash_password))
+    }
+
+    fn get(&self, id_account: u64) -> Result<Account, ActixError> {
+        self.with(|conn| accounts::get_account_by_id_data(conn, id_account))
+    }
+
+    fn get_by_username(&self, username: &str) -> Result<Account, ActixError> {
+        self.with(|conn| accounts::get_account_by_username_data(conn, username))
+    }
+
+    fn list(&self, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError> {
+        self.with(|conn| accounts::get_account_data(conn, offset, limit))
+    }
+
+    fn update_username(&self, id_account: u64, username: &str) -> Result<Account, ActixError> {
+        self.with(|conn| accounts::update_account_username_data(conn, id_account, username))
+    }
+
+    fn update_password(&self, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+        self.with(|conn| accounts::update_account_password_data(conn, id_account, hash_password))
+    }
+
+    fn delete(&self, id_account: u64) -> Result<(), ActixError> {
+        self.with(|conn| accounts::delete_account_data(conn, id_account))
+    }
+
+    fn restore(&self, id_account: u64) -> Result<Account, ActixError> {
+        self.with(|conn| accounts::restore_account_data(conn, id_account))
+    }
+}
+
+impl AccountChangeFeed for PostgresDatabase {
+    fn now(&self) -> Result<i64, ActixError> {
+        self.with(accounts::get_database_time)
+    }
+
+    fn changes_since(&self, since: i64) -> Result<(Vec<AccountChange>, i64), ActixError> {
+        let (rows, now) = self.with(|conn| accounts::get_account_changes(conn, since))?;
+        let changes = rows.into_iter()
+            .map(|(account, deleted)| if deleted {
+                AccountChange::Deleted(account.id)
+            } else {
+                AccountChange::Saved(account)
+            })
+            .collect();
+
+        Ok((changes, now))
+    }
+}
+
+impl Database for PostgresDatabase {
+    fn get_account_authorizations(&self, id_account: u64) -> Result<(Vec<String>, Vec<String>), ActixError> {
+        self.with(|conn| roles::get_account_authorizations(conn, id_account))
+    }
+
+    fn insert_access_token(&self, id_account: u64, access_token: &str, refresh_token: &str, family: &str) -> Result<AccessToken, ActixError> {
+        self.with(|conn| access::insert_access_token_data(conn, id_account, access_token, refresh_token, family))
+    }
+
+    fn get_refresh_token(&self, refresh_token: &str) -> Result<Option<RefreshTokenRow>, ActixError> {
+        self.with(|conn| access::get_refresh_token(conn, refresh_token))
+    }
+
+    fn revoke_token_pair(&self, id: u64) -> Result<bool, ActixError> {
+        self.with(|conn| access::revoke_token_pair(conn, id))
+    }
+
+    fn revoke_token_family(&self, family: &str) -> Result<(), ActixError> {
+        self.with(|conn| access::revoke_token_family(conn, family))
+    }
+
+    fn revoke_access_token(&self, access_token: &str) -> Result<(), ActixError> {
+        self.with(|conn| access::revoke_access_token_data(conn, access_token))
+    }
+
+    fn revoke_account_tokens(&self, id_account: u64) -> Result<Vec<(String, i64)>, ActixError> {
+        self.with(|conn| access::revoke_account_tokens(conn, id_account))
+    }
+
+    fn get_revoked_access_tokens(&self) -> Result<Vec<(String, i64)>, ActixError> {
+        self.with(access::get_revoked_access_tokens)
+    }
+
+    fn get_totp(&self, id_account: u64) -> Result<Option<AccountTotp>, ActixError> {
+        self.with(|conn| two_factor::get_totp_data(conn, id_account))
+    }
+
+    fn save_pending_totp(&self, id_account: u64, secret: &str) -> Result<(), ActixError> {
+        self.with(|conn| two_factor::save_pending_totp(conn, id_account, secret))
+    }
+
+    fn enable_totp(&self, id_account: u64, recovery_codes: &[String]) -> Result<(), ActixError> {
+        self.with(|conn| two_factor::enable_totp(conn, id_account, recovery_codes))
+    }
+
+    fn disable_totp(&self, id_account: u64) -> Result<(), ActixError> {
+        self.with(|conn| two_factor::disable_totp(conn, id_account))
+    }
+
+    fn verify_second_factor(&self, account_totp: &AccountTotp, code: &str) -> Result<(), ActixError> {
+        self.with(|conn| two_factor::verify_second_factor(conn, account_totp, code))
+    }
+
+    fn create_password_reset(&self, id_account: u64, token_hash: &str) -> Result<(), ActixError> {
+        self.with(|conn| password_reset::create_password_reset(conn, id_account, token_hash))
+    }
+
+    fn get_password_reset(&self, token_hash: &str) -> Result<u64, ActixError> {
+        self.with(|conn| password_reset::get_password_reset(conn, token_hash))
+    }
+
+    fn reset_password(&self, token_hash: &str, hash_password: &str) -> Result<Account, ActixError> {
+        self.with(|conn| password_reset::reset_password(conn, token_hash, hash_password))
+    }
+
+    fn get_login_attempts(&self, window_seconds: i64) -> Result<Vec<LoginAttemptRow>, ActixError> {
+        self.with(|conn| login_attempts::get_login_attempts(conn, window_seconds))
+    }
+
+    fn save_login_attempt(&self, attempt: &LoginAttemptRow) -> Result<(), ActixError> {
+        self.with(|conn| login_attempts::save_login_attempt(conn, attempt))
+    }
+
+    fn delete_login_attempt(&self, attempt_key: &str) -> Result<(), ActixError> {
+        self.with(|conn| login_attempts::delete_login_attempt(conn, attempt_key))
+    }
+
+    fn purge_deleted_accounts(&self, retention_days: u32) -> Result<u64, ActixError> {
+        self.with(|conn| accounts::purge_deleted_accounts(conn, retention_days))
+    }
+
+    fn dialect(&self) -> Dialect {
+        Dialect::Postgres
+    }
+
+    fn lock_migrations(&self) -> Result<(), ActixError> {
+        self.with(migrations::lock_migrations)
+    }
+
+    fn unlock_migrations(&self) -> Result<(), ActixError> {
+        self.with(migrations::unlock_migrations)
+    }
+
+    fn create_migrations_table(&self) -> Result<(), ActixError> {
+        self.with(migrations::create_migrations_table)
+    }
+
+    fn has_table(&self, table: &str) -> Result<bool, ActixError> {
+        self.with(|conn| migrations::has_table(conn, table))
+    }
+
+    fn get_applied_migrations(&self) -> Result<Vec<(u32, String)>, ActixError> {
+        self.with(migrations::get_applied_migrations)
+    }
+
+    fn apply_migration(&self, migration: &Migration) -> Result<(), ActixError> {
+        self.with(|conn| migrations::apply_migration(conn, migration))
+    }
+
+    fn revert_migration(&self, migration: &Migration) -> Result<(), ActixError> {
+        self.with(|conn| migrations::revert_migration(conn, migration))
+    }
+
+    fn mark_migration_applied(&self, migration: &Migration) -> Result<(), ActixError> {
+        self.with(|conn| migrations::mark_migration_applied(conn, migration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use crate::migrations::{run, MigrateArgs, MigrateCommand, MIGRATIONS};
+    use crate::persistance::suite;
+
+    fn migrate(db: &PostgresDatabase, command: MigrateCommand) {
+        run(db, &MigrateArgs { command, dry_run: false }).unwrap();
+    }
+
+    // Runs the shared cases on an empty database, which it leaves empty again:
+    // `POSTGRES_URL=postgres://postgres@localhost/nyu_model_test cargo test --features postgres -- --ignored`.
+    // Runs on an actix system thread, like the startup code does.
+    #[actix_web::test]
+    #[ignore]
+    async fn behaves_like_the_other_backends() {
+        let url = env::var("POSTGRES_URL").expect("POSTGRES_URL must be set");
+        let db = PostgresDatabase::connect(&url).unwrap();
+        let other = PostgresDatabase::connect(&url).unwrap();
+        // Instances starting together wait for each other's migrations instead of failing.
+        thread::scope(|scope| {
+            let migrating = scope.spawn(|| run(&other, &MigrateArgs { command: MigrateCommand::Up, dry_run: false }));
+            migrate(&db, MigrateCommand::Up);
+            migrating.join().unwrap().unwrap();
+        });
+        assert_eq!(db.get_applied_migrations().unwrap().len(), MIGRATIONS.len());
+        migrate(&db, MigrateCommand::Down(MIGRATIONS.len()));
+        assert!(db.get_applied_migrations().unwrap().is_empty());
+
+        for case in suite::CASES {
+            migrate(&db, MigrateCommand::Up);
+            case(&db);
+            migrate(&db, MigrateCommand::Down(MIGRATIONS.len()));
+        }
+
+        migrate(&db, MigrateCommand::Up);
+        suite::purged_accounts_take_their_login_attempts_along(&db, || {
+            db.with(|conn| Ok(conn.execute("UPDATE accounts SET deleted_at = CURRENT_TIMESTAMP - INTERVAL '40 days' WHERE deleted_at IS NOT NULL", &[])?)).unwrap();
+        });
+        migrate(&db, MigrateCommand::Down(MIGRATIONS.len()));
+    }
+}
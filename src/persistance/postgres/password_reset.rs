@@ -0,0 +1,45 @@
+use crate::models::Account;
+use crate::errors::ActixError;
+use crate::persistance::PASSWORD_RESET_LIFETIME_MINUTES;
+use super::accounts::update_account_password_data;
+use postgres::GenericClient;
+
+pub fn create_password_reset(conn: &mut impl GenericClient, id_account: u64, token_hash: &str) -> Result<(), ActixError> {
+    conn.execute(
+        "INSERT INTO password_reset (id_account,token_hash,expires_at) VALUES ($1,$2,CURRENT_TIMESTAMP + $3::BIGINT * INTERVAL '1 minute')",
+        &[&(id_account as i64), &token_hash, &PASSWORD_RESET_LIFETIME_MINUTES],
+    )?;
+
+    Ok(())
+}
+
+pub fn get_password_reset(conn: &mut impl GenericClient, token_hash: &str) -> Result<u64, ActixError> {
+    select_valid_password_reset(conn, token_hash)?.ok_or(ActixError::UnAuthorized)
+}
+
+// Marks the token as used and stores the new password of its account; every other pending token
+// of the account is burnt too.
+pub fn reset_password(conn: &mut impl GenericClient, token_hash: &str, hash_password: &str) -> Result<Account, ActixError> {
+    let mut tx = conn.transaction()?;
+
+    let id_account = select_valid_password_reset(&mut tx, token_hash)?.ok_or(ActixError::UnAuthorized)?;
+    tx.execute(
+        "UPDATE password_reset SET used_at = CURRENT_TIMESTAMP WHERE id_account = $1 AND used_at IS NULL",
+        &[&(id_account as i64)],
+    )?;
+    let account = update_account_password_data(&mut tx, id_account, hash_password)?;
+    tx.commit()?;
+
+    Ok(account)
+}
+
+fn select_valid_password_reset(conn: &mut impl GenericClient, token_hash: &str) -> Result<Option<u64>, postgres::Error> {
+    let row = conn.query_opt(
+        "SELECT id_account FROM password_reset
+         WHERE token_hash = $1 AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+         FOR UPDATE",
+        &[&token_hash],
+    )?;
+
+    Ok(row.map(|row| row.get::<_, i64>(0) as u64))
+}
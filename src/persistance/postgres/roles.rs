@@ -0,0 +1,43 @@
+use crate::errors::ActixError;
+use postgres::GenericClient;
+
+pub fn get_account_authorizations(
+    conn: &mut impl GenericClient,
+    id_account: u64,
+) -> Result<(Vec<String>, Vec<String>), ActixError> {
+    let roles = select_names(
+        conn,
+        "SELECT r.name FROM roles r
+         JOIN account_roles ar ON ar.id_role = r.id
+         WHERE ar.id_account = $1 ORDER BY r.name ASC",
+        id_account,
+    )?;
+    let permissions = select_names(
+        conn,
+        "SELECT DISTINCT p.name FROM permissions p
+         JOIN role_permissions rp ON rp.id_permission = p.id
+         JOIN account_roles ar ON ar.id_role = rp.id_role
+         WHERE ar.id_account = $1 ORDER BY p.name ASC",
+        id_account,
+    )?;
+
+    Ok((roles, permissions))
+}
+
+pub fn insert_account_role(
+    conn: &mut impl GenericClient,
+    id_account: u64,
+    role: &str,
+) -> Result<(), postgres::Error> {
+    conn.execute(
+        "INSERT INTO account_roles (id_account,id_role) SELECT $1, id FROM roles WHERE name = $2",
+        &[&(id_account as i64), &role],
+    )
+    .map(|_| ())
+}
+
+fn select_names(conn: &mut impl GenericClient, query: &str, id_account: u64) -> Result<Vec<String>, postgres::Error> {
+    let rows = conn.query(query, &[&(id_account as i64)])?;
+
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
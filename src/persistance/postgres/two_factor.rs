@@ -0,0 +1,86 @@
+use crate::errors::ActixError;
+use crate::models::AccountTotp;
+use crate::password::hash_token;
+use crate::persistance::{second_factor, SecondFactor};
+use postgres::GenericClient;
+
+pub fn get_totp_data(conn: &mut impl GenericClient, id_account: u64) -> Result<Option<AccountTotp>, ActixError> {
+    let row = conn.query_opt(
+        "SELECT id_account, secret, enabled FROM account_totp WHERE id_account = $1",
+        &[&(id_account as i64)],
+    )?;
+
+    Ok(row.map(|row| AccountTotp {
+        id_account: row.get::<_, i64>(0) as u64,
+        secret: row.get(1),
+        enabled: row.get(2),
+    }))
+}
+
+pub fn save_pending_totp(conn: &mut impl GenericClient, id_account: u64, secret: &str) -> Result<(), ActixError> {
+    conn.execute(
+        "INSERT INTO account_totp (id_account,secret) VALUES ($1,$2)
+         ON CONFLICT (id_account) DO UPDATE SET secret = $2, enabled = FALSE, last_used_step = 0",
+        &[&(id_account as i64), &secret],
+    )?;
+
+    Ok(())
+}
+
+pub fn enable_totp(conn: &mut impl GenericClient, id_account: u64, recovery_codes: &[String]) -> Result<(), ActixError> {
+    let mut tx = conn.transaction()?;
+
+    tx.execute(
+        "UPDATE account_totp SET enabled = TRUE WHERE id_account = $1",
+        &[&(id_account as i64)],
+    )?;
+    delete_recovery_codes(&mut tx, id_account)?;
+    let insert = tx.prepare("INSERT INTO recovery_codes (id_account,code_hash) VALUES ($1,$2)")?;
+    for code in recovery_codes {
+        tx.execute(&insert, &[&(id_account as i64), &hash_token(code)])?;
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+pub fn disable_totp(conn: &mut impl GenericClient, id_account: u64) -> Result<(), ActixError> {
+    let mut tx = conn.transaction()?;
+
+    tx.execute(
+        "DELETE FROM account_totp WHERE id_account = $1",
+        &[&(id_account as i64)],
+    )?;
+    delete_recovery_codes(&mut tx, id_account)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+// Accepts either a TOTP code or an unused recovery code. Both are burnt once accepted.
+pub fn verify_second_factor(conn: &mut impl GenericClient, account_totp: &AccountTotp, code: &str) -> Result<(), ActixError> {
+    let accepted = match second_factor(account_totp, code) {
+        SecondFactor::TotpStep(step) => conn.execute(
+            "UPDATE account_totp SET last_used_step = $2 WHERE id_account = $1 AND last_used_step < $2",
+            &[&(account_totp.id_account as i64), &(step as i64)],
+        )? > 0,
+        SecondFactor::RecoveryCode(code_hash) => conn.execute(
+            "UPDATE recovery_codes SET used_at = CURRENT_TIMESTAMP WHERE id_account = $1 AND code_hash = $2 AND used_at IS NULL",
+            &[&(account_totp.id_account as i64), &code_hash],
+        )? > 0,
+        SecondFactor::Invalid => false,
+    };
+
+    if accepted {
+        Ok(())
+    } else {
+        Err(ActixError::UnAuthorized)
+    }
+}
+
+fn delete_recovery_codes(conn: &mut impl GenericClient, id_account: u64) -> Result<u64, postgres::Error> {
+    conn.execute(
+        "DELETE FROM recovery_codes WHERE id_account = $1",
+        &[&(id_account as i64)],
+    )
+}
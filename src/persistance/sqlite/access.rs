@@ -0,0 +1,122 @@
+use crate::models::AccessToken;
+use crate::persistance::RefreshTokenRow;
+use crate::tokens::ACCESS_TOKEN_LIFETIME_MINUTES;
+use crate::errors::ActixError;
+use rusqlite::{named_params, Connection, OptionalExtension};
+
+pub fn insert_access_token_data(
+    conn: &Connection,
+    id_account: u64,
+    access_token: &str,
+    refresh_token: &str,
+    family: &str,
+) -> Result<AccessToken, ActixError> {
+    conn.execute(
+        "INSERT INTO access_token (id_account,access_token,refresh_token,family) VALUES (:id_account,:access_token,:refresh_token,:family)",
+        named_params! {
+            ":id_account": id_account,
+            ":access_token": access_token,
+            ":refresh_token": refresh_token,
+            ":family": family,
+        },
+    )?;
+
+    Ok(AccessToken{
+        id: conn.last_insert_rowid() as u64,
+        access_token: access_token.to_string(),
+        id_account,
+        refresh_token: refresh_token.to_string(),
+    })
+}
+
+pub fn get_refresh_token(conn: &Connection, refresh_token: &str) -> Result<Option<RefreshTokenRow>, ActixError> {
+    Ok(conn.query_row(
+        "SELECT id, id_account, family, revoked_at IS NOT NULL FROM access_token WHERE refresh_token = :refresh_token",
+        named_params! {
+            ":refresh_token": refresh_token,
+        },
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )
+    .optional()?)
+}
+
+pub fn revoke_token_pair(conn: &Connection, id: u64) -> Result<bool, ActixError> {
+    let revoked = conn.execute(
+        "UPDATE access_token SET revoked_at = CURRENT_TIMESTAMP WHERE id = :id AND revoked_at IS NULL",
+        named_params! {
+            ":id": id,
+        },
+    )?;
+
+    Ok(revoked > 0)
+}
+
+pub fn revoke_token_family(conn: &Connection, family: &str) -> Result<(), ActixError> {
+    conn.execute(
+        "UPDATE access_token SET revoked_at = CURRENT_TIMESTAMP WHERE family = :family AND revoked_at IS NULL",
+        named_params! {
+            ":family": family,
+        },
+    )?;
+
+    Ok(())
+}
+
+pub fn revoke_access_token_data(conn: &Connection, access_token: &str) -> Result<(), ActixError> {
+    conn.execute(
+        "UPDATE access_token SET revoked_at = CURRENT_TIMESTAMP WHERE access_token = :access_token AND revoked_at IS NULL",
+        named_params! {
+            ":access_token": access_token,
+        },
+    )?;
+
+    Ok(())
+}
+
+pub fn revoke_account_tokens(conn: &mut Connection, id_account: u64) -> Result<Vec<(String, i64)>, ActixError> {
+    let tx = conn.transaction()?;
+
+    let active_tokens = select_active_access_tokens(&tx, id_account)?;
+    tx.execute(
+        "UPDATE access_token SET revoked_at = CURRENT_TIMESTAMP WHERE id_account = :id_account AND revoked_at IS NULL",
+        named_params! {
+            ":id_account": id_account,
+        },
+    )?;
+    tx.commit()?;
+
+    Ok(active_tokens)
+}
+
+pub fn get_revoked_access_tokens(conn: &Connection) -> Result<Vec<(String, i64)>, ActixError> {
+    let mut statement = conn.prepare(
+        "SELECT access_token, unixepoch(date_added) + :lifetime FROM access_token
+         WHERE revoked_at IS NOT NULL AND date_added > datetime('now', :age)",
+    )?;
+    let tokens = statement.query_map(
+        named_params! {
+            ":lifetime": ACCESS_TOKEN_LIFETIME_MINUTES * 60,
+            ":age": format!("-{} minutes", ACCESS_TOKEN_LIFETIME_MINUTES),
+        },
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    Ok(tokens.collect::<rusqlite::Result<_>>()?)
+}
+
+fn select_active_access_tokens(conn: &Connection, id_account: u64) -> rusqlite::Result<Vec<(String, i64)>> {
+    let mut statement = conn.prepare(
+        "SELECT access_token, unixepoch(date_added) + :lifetime FROM access_token
+         WHERE id_account = :id_account AND revoked_at IS NULL AND date_added > datetime('now', :age)",
+    )?;
+    let tokens = statement.query_map(
+        named_params! {
+            ":id_account": id_account,
+            ":lifetime": ACCESS_TOKEN_LIFETIME_MINUTES * 60,
+            ":age": format!("-{} minutes", ACCESS_TOKEN_LIFETIME_MINUTES),
+        },
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    tokens.collect()
+}
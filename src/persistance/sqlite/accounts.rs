@@ -0,0 +1,222 @@
+use crate::models::Account;
+use crate::errors::ActixError;
+use crate::persistance::DEFAULT_ROLE;
+use super::roles::insert_account_role;
+use rusqlite::{named_params, Connection, OptionalExtension, Row};
+
+pub fn create_new_account(
+    conn: &mut Connection,
+    username: &str,
+    hash_password: &str,
+) -> Result<Account, ActixError> {
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "INSERT INTO accounts (username,password) VALUES (:username,:password)",
+        named_params! {
+            ":username": username,
+            ":password": hash_password,
+        },
+    )
+    .map_err(duplicate_username)?;
+    let id = tx.last_insert_rowid() as u64;
+    insert_account_role(&tx, id, DEFAULT_ROLE)?;
+    tx.commit()?;
+
+    Ok(Account{
+        id,
+        password: String::from(hash_password),
+        username: String::from(username),
+    })
+}
+
+// SQLite reads a negative limit as no limit at all.
+pub fn get_account_data(conn: &Connection, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError> {
+    let mut statement = conn.prepare(
+        "SELECT id, username, password FROM accounts WHERE deleted_at IS NULL ORDER BY id ASC LIMIT :limit OFFSET :offset",
+    )?;
+    let accounts = statement.query_map(
+        named_params! {
+            ":limit": i64::try_from(limit).unwrap_or(-1),
+            ":offset": i64::try_from(offset).unwrap_or(i64::MAX),
+        },
+        account_from_row,
+    )?;
+
+    Ok(accounts.collect::<rusqlite::Result<_>>()?)
+}
+
+pub fn get_account_by_id_data(conn: &Connection, id_account: u64) -> Result<Account, ActixError> {
+    select_account_by_id(conn, id_account)?.ok_or(ActixError::NotFound)
+}
+
+pub fn get_account_by_username_data(conn: &Connection, username: &str) -> Result<Account, ActixError> {
+    select_account_by_username(conn, username)?.ok_or(ActixError::NotFound)
+}
+
+pub fn update_account_username_data(conn: &Connection, id_account: u64, username: &str) -> Result<Account, ActixError> {
+    update_account_column(conn, id_account, "username", username)
+        .map_err(duplicate_username)?
+        .ok_or(ActixError::NotFound)
+}
+
+pub fn update_account_password_data(conn: &Connection, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+    update_account_column(conn, id_account, "password", hash_password)?.ok_or(ActixError::NotFound)
+}
+
+pub fn delete_account_data(conn: &Connection, id_account: u64) -> Result<(), ActixError> {
+    let deleted = conn.execute(
+        "UPDATE accounts SET deleted_at = CURRENT_TIMESTAMP WHERE id = :id AND deleted_at IS NULL",
+        named_params! {
+            ":id": id_account,
+        },
+    )?;
+
+    match deleted {
+        0 => Err(ActixError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+pub fn get_database_time(conn: &Connection) -> Result<i64, ActixError> {
+    Ok(conn.query_row("SELECT unixepoch()", [], |row| row.get(0))?)
+}
+
+// Accounts whose row changed at or after `since` (seconds since epoch) with whether they are
+// deleted, and the database time the lookup was made at.
+pub fn get_account_changes(conn: &Connection, since: i64) -> Result<(Vec<(Account, bool)>, i64), ActixError> {
+    let now = get_database_time(conn)?;
+
+    let mut statement = conn.prepare(
+        "SELECT id, username, password, deleted_at IS NOT NULL FROM accounts WHERE updated_at >= datetime(:since, 'unixepoch')",
+    )?;
+    let changes = statement.query_map(
+        named_params! {
+            ":since": since,
+        },
+        |row| Ok((account_from_row(row)?, row.get(3)?)),
+    )?;
+
+    Ok((changes.collect::<rusqlite::Result<_>>()?, now))
+}
+
+// Refused when another account took the username in the meantime.
+pub fn restore_account_data(conn: &mut Connection, id_account: u64) -> Result<Account, ActixError> {
+    let tx = conn.transaction()?;
+
+    let account = tx.query_row(
+        "SELECT id, username, password FROM accounts WHERE id = :id AND deleted_at IS NOT NULL",
+        named_params! {
+            ":id": id_account,
+        },
+        account_from_row,
+    )
+    .optional()?
+    .ok_or(ActixError::NotFound)?;
+    if select_account_by_username(&tx, &account.username)?.is_some() {
+        return Err(ActixError::SameAccountName);
+    }
+
+    tx.execute(
+        "UPDATE accounts SET deleted_at = NULL WHERE id = :id AND deleted_at IS NOT NULL",
+        named_params! {
+            ":id": id_account,
+        },
+    )
+    .map_err(duplicate_username)?;
+    tx.commit()?;
+
+    Ok(account)
+}
+
+pub fn purge_deleted_accounts(conn: &mut Connection, retention_days: u32) -> Result<u64, ActixError> {
+    let tx = conn.transaction()?;
+    let retention = format!("-{} days", retention_days);
+
+    // Attempts are keyed by username, which an active account may have taken again since.
+    tx.execute(
+        "DELETE FROM login_attempts WHERE attempt_key IN
+         (SELECT 'account:' || purged.username FROM accounts purged
+          WHERE purged.deleted_at < datetime('now', :retention)
+          AND NOT EXISTS (SELECT 1 FROM accounts active WHERE active.username = purged.username AND active.deleted_at IS NULL))",
+        named_params! {
+            ":retention": retention,
+        },
+    )?;
+    for table in ["access_token", "account_roles", "password_reset", "account_totp", "recovery_codes"] {
+        tx.execute(
+            &format!(
+                "DELETE FROM {} WHERE id_account IN
+                 (SELECT id FROM accounts WHERE deleted_at < datetime('now', :retention))",
+                table,
+            ),
+            named_params! {
+                ":retention": retention,
+            },
+        )?;
+    }
+    let purged = tx.execute(
+        "DELETE FROM accounts WHERE deleted_at < datetime('now', :retention)",
+        named_params! {
+            ":retention": retention,
+        },
+    )?;
+    tx.commit()?;
+
+    Ok(purged as u64)
+}
+
+// `idx_accounts_active_username` keeps usernames unique among accounts that are not deleted.
+fn duplicate_username(err: rusqlite::Error) -> ActixError {
+    match err {
+        rusqlite::Error::SqliteFailure(ref failure, _) if failure.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE => {
+            ActixError::SameAccountName
+        }
+        err => ActixError::SqliteError(err),
+    }
+}
+
+fn update_account_column(conn: &Connection, account_id: u64, column: &str, value: &str) -> rusqlite::Result<Option<Account>> {
+    conn.query_row(
+        &format!(
+            "UPDATE accounts SET {} = :value WHERE id = :id AND deleted_at IS NULL RETURNING id, username, password",
+            column,
+        ),
+        named_params! {
+            ":id": account_id,
+            ":value": value,
+        },
+        account_from_row,
+    )
+    .optional()
+}
+
+fn select_account_by_id(conn: &Connection, account_id: u64) -> rusqlite::Result<Option<Account>> {
+    conn.query_row(
+        "SELECT id, username, password FROM accounts WHERE id = :id AND deleted_at IS NULL",
+        named_params! {
+            ":id": account_id,
+        },
+        account_from_row,
+    )
+    .optional()
+}
+
+fn select_account_by_username(conn: &Connection, username: &str) -> rusqlite::Result<Option<Account>> {
+    conn.query_row(
+        "SELECT id, username, password FROM accounts WHERE username = :username AND deleted_at IS NULL",
+        named_params! {
+            ":username": username,
+        },
+        account_from_row,
+    )
+    .optional()
+}
+
+fn account_from_row(row: &Row) -> rusqlite::Result<Account> {
+    Ok(Account {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        password: row.get(2)?,
+    })
+}
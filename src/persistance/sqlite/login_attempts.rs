@@ -0,0 +1,48 @@
+use crate::errors::ActixError;
+use crate::persistance::LoginAttemptRow;
+use rusqlite::{named_params, Connection};
+
+pub fn get_login_attempts(conn: &Connection, window_seconds: i64) -> Result<Vec<LoginAttemptRow>, ActixError> {
+    let mut statement = conn.prepare(
+        "SELECT attempt_key, failures, unixepoch(blocked_until), unixepoch(last_failure) FROM login_attempts
+         WHERE blocked_until > datetime('now') OR last_failure > datetime('now', :window)",
+    )?;
+    let attempts = statement.query_map(
+        named_params! {
+            ":window": format!("-{} seconds", window_seconds),
+        },
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+
+    Ok(attempts.collect::<rusqlite::Result<_>>()?)
+}
+
+pub fn save_login_attempt(
+    conn: &Connection,
+    (attempt_key, failures, blocked_until, last_failure): &LoginAttemptRow,
+) -> Result<(), ActixError> {
+    conn.execute(
+        "INSERT INTO login_attempts (attempt_key,failures,blocked_until,last_failure)
+         VALUES (:attempt_key,:failures,datetime(:blocked_until, 'unixepoch'),datetime(:last_failure, 'unixepoch'))
+         ON CONFLICT (attempt_key) DO UPDATE SET failures = :failures, blocked_until = datetime(:blocked_until, 'unixepoch'), last_failure = datetime(:last_failure, 'unixepoch')",
+        named_params! {
+            ":attempt_key": attempt_key,
+            ":failures": failures,
+            ":blocked_until": blocked_until,
+            ":last_failure": last_failure,
+        },
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_login_attempt(conn: &Connection, attempt_key: &str) -> Result<(), ActixError> {
+    conn.execute(
+        "DELETE FROM login_attempts WHERE attempt_key = :attempt_key",
+        named_params! {
+            ":attempt_key": attempt_key,
+        },
+    )?;
+
+    Ok(())
+}
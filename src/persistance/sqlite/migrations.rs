@@ -0,0 +1,78 @@
+use crate::errors::ActixError;
+use crate::migrations::{Dialect, Migration};
+use rusqlite::{named_params, Connection};
+
+pub fn create_migrations_table(conn: &Connection) -> Result<(), ActixError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name VARCHAR(255) NOT NULL,
+            checksum CHAR(64) NOT NULL,
+            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+pub fn has_table(conn: &Connection, table: &str) -> Result<bool, ActixError> {
+    let tables: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = :table",
+        named_params! { ":table": table },
+        |row| row.get(0),
+    )?;
+
+    Ok(tables > 0)
+}
+
+// (version, checksum) of every applied migration, by version.
+pub fn get_applied_migrations(conn: &Connection) -> Result<Vec<(u32, String)>, ActixError> {
+    let mut statement = conn.prepare("SELECT version, checksum FROM schema_migrations ORDER BY version")?;
+    let applied = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    Ok(applied.collect::<rusqlite::Result<_>>()?)
+}
+
+// Schema changes are transactional in SQLite: a failing migration leaves nothing behind.
+pub fn apply_migration(conn: &mut Connection, migration: &Migration) -> Result<(), ActixError> {
+    let tx = conn.transaction()?;
+
+    tx.execute_batch(migration.script(Dialect::Sqlite).up)?;
+    insert_migration(&tx, migration)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+pub fn revert_migration(conn: &mut Connection, migration: &Migration) -> Result<(), ActixError> {
+    let tx = conn.transaction()?;
+
+    tx.execute_batch(migration.script(Dialect::Sqlite).down)?;
+    tx.execute(
+        "DELETE FROM schema_migrations WHERE version = :version",
+        named_params! {
+            ":version": migration.version,
+        },
+    )?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+// Records the migration as applied without running it.
+pub fn mark_migration_applied(conn: &Connection, migration: &Migration) -> Result<(), ActixError> {
+    Ok(insert_migration(conn, migration)?)
+}
+
+fn insert_migration(conn: &Connection, migration: &Migration) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO schema_migrations (version,name,checksum) VALUES (:version,:name,:checksum)",
+        named_params! {
+            ":version": migration.version,
+            ":name": migration.name,
+            ":checksum": migration.checksum(Dialect::Sqlite),
+        },
+    )
+    .map(|_| ())
+}
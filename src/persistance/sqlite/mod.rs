@@ -0,0 +1,267 @@
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use rusqlite::Connection;
+use crate::accounts::{AccountChange, AccountChangeFeed, AccountStore};
+use crate::errors::ActixError;
+use crate::migrations::{Dialect, Migration};
+use crate::models::{AccessToken, Account, AccountTotp};
+use super::{Database, LoginAttemptRow, RefreshTokenRow};
+
+mod access;
+mod accounts;
+mod login_attempts;
+mod migrations;
+mod password_reset;
+mod roles;
+mod two_factor;
+
+// Every request goes through a single connection. That is enough for development and tests, and
+// the only way to share an in-memory database.
+pub struct SqliteDatabase {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteDatabase {
+    // `:memory:` opens a new, empty in-memory database.
+    pub fn open(path: &str) -> Result<SqliteDatabase, String> {
+        let connection = Connection::open(path).map_err(|err| err.to_string())?;
+        connection.busy_timeout(Duration::from_secs(5)).map_err(|err| err.to_string())?;
+
+        Ok(SqliteDatabase { connection: Mutex::new(connection) })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap()
+    }
+
+    // Lets tests put rows in states the API never leaves them in, like expired tokens.
+    #[cfg(test)]
+    pub(crate) fn execute(&self, sql: &str) {
+        self.connection().execute_batch(sql).unwrap();
+    }
+}
+
+impl AccountStore for SqliteDatabase {
+    fn create(&self, username: &str, hash_password: &str) -> Result<Account, ActixError> {
+        accounts::create_new_account(&mut self.connection(), username, hash_password)
+    }
+
+    fn get(&self, id_account: u64) -> Result<Account, ActixError> {
+        accounts::get_account_by_id_data(&self.connection(), id_account)
+    }
+
+    fn get_by_username(&self, username: &str) -> Result<Account, ActixError> {
+        accounts::get_account_by_username_data(&self.connection(), username)
+    }
+
+    fn list(&self, offset: usize, limit: usize) -> Result<Vec<Account>, ActixError> {
+        accounts::get_account_data(&self.connection(), offset, limit)
+    }
+
+    fn update_username(&self, id_account: u64, username: &str) -> Result<Account, ActixError> {
+        accounts::update_account_username_data(&self.connection(), id_account, username)
+    }
+
+    fn update_password(&self, id_account: u64, hash_password: &str) -> Result<Account, ActixError> {
+        accounts::update_account_password_data(&self.connection(), id_account, hash_password)
+    }
+
+    fn delete(&self, id_account: u64) -> Result<(), ActixError> {
+        accounts::delete_account_data(&self.connection(), id_account)
+    }
+
+    fn restore(&self, id_account: u64) -> Result<Account, ActixError> {
+        accounts::restore_account_data(&mut self.connection(), id_account)
+    }
+}
+
+impl AccountChangeFeed for SqliteDatabase {
+    fn now(&self) -> Result<i64, ActixError> {
+        accounts::get_database_time(&self.connection())
+    }
+
+    fn changes_since(&self, since: i64) -> Result<(Vec<AccountChange>, i64), ActixError> {
+        let (rows, now) = accounts::get_account_changes(&self.connection(), since)?;
+        let changes = rows.into_iter()
+            .map(|(account, deleted)| if deleted {
+                AccountChange::Deleted(account.id)
+            } else {
+                AccountChange::Saved(account)
+            })
+            .collect();
+
+        Ok((changes, now))
+    }
+}
+
+impl Database for SqliteDatabase {
+    fn get_account_authorizations(&self, id_account: u64) -> Result<(Vec<String>, Vec<String>), ActixError> {
+        roles::get_account_authorizations(&self.connection(), id_account)
+    }
+
+    fn insert_access_token(&self, id_account: u64, access_token: &str, refresh_token: &str, family: &str) -> Result<AccessToken, ActixError> {
+        access::insert_access_token_data(&self.connection(), id_account, access_token, refresh_token, family)
+    }
+
+    fn get_refresh_token(&self, refresh_token: &str) -> Result<Option<RefreshTokenRow>, ActixError> {
+        access::get_refresh_token(&self.connection(), refresh_token)
+    }
+
+    fn revoke_token_pair(&self, id: u64) -> Result<bool, ActixError> {
+        access::revoke_token_pair(&self.connection(), id)
+    }
+
+    fn revoke_token_family(&self, family: &str) -> Result<(), ActixError> {
+        access::revoke_token_family(&self.connection(), family)
+    }
+
+    fn revoke_access_token(&self, access_token: &str) -> Result<(), ActixError> {
+        access::revoke_access_token_data(&self.connection(), access_token)
+    }
+
+    fn revoke_account_tokens(&self, id_account: u64) -> Result<Vec<(String, i64)>, ActixError> {
+        access::revoke_account_tokens(&mut self.connection(), id_account)
+    }
+
+    fn get_revoked_access_tokens(&self) -> Result<Vec<(String, i64)>, ActixError> {
+        access::get_revoked_access_tokens(&self.connection())
+    }
+
+    fn get_totp(&self, id_account: u64) -> Result<Option<AccountTotp>, ActixError> {
+        two_factor::get_totp_data(&self.connection(), id_account)
+    }
+
+    fn save_pending_totp(&self, id_account: u64, secret: &str) -> Result<(), ActixError> {
+        two_factor::save_pending_totp(&self.connection(), id_account, secret)
+    }
+
+    fn enable_totp(&self, id_account: u64, recovery_codes: &[String]) -> Result<(), ActixError> {
+        two_factor::enable_totp(&mut self.connection(), id_account, recovery_codes)
+    }
+
+    fn disable_totp(&self, id_account: u64) -> Result<(), ActixError> {
+        two_factor::disable_totp(&mut self.connection(), id_account)
+    }
+
+    fn verify_second_factor(&self, account_totp: &AccountTotp, code: &str) -> Result<(), ActixError> {
+        two_factor::verify_second_factor(&self.connection(), account_totp, code)
+    }
+
+    fn create_password_reset(&self, id_account: u64, token_hash: &str) -> Result<(), ActixError> {
+        password_reset::create_password_reset(&self.connection(), id_account, token_hash)
+    }
+
+    fn get_password_reset(&self, token_hash: &str) -> Result<u64, ActixError> {
+        password_reset::get_password_reset(&self.connection(), token_hash)
+    }
+
+    fn reset_password(&self, token_hash: &str, hash_password: &str) -> Result<Account, ActixError> {
+        password_reset::reset_password(&mut self.connection(), token_hash, hash_password)
+    }
+
+    fn get_login_attempts(&self, window_seconds: i64) -> Result<Vec<LoginAttemptRow>, ActixError> {
+        login_attempts::get_login_attempts(&self.connection(), window_seconds)
+    }
+
+    fn save_login_attempt(&self, attempt: &LoginAttemptRow) -> Result<(), ActixError> {
+        login_attempts::save_login_attempt(&self.connection(), attempt)
+    }
+
+    fn delete_login_attempt(&self, attempt_key: &str) -> Result<(), ActixError> {
+        login_attempts::delete_login_attempt(&self.connection(), attempt_key)
+    }
+
+    fn purge_deleted_accounts(&self, retention_days: u32) -> Result<u64, ActixError> {
+        accounts::purge_deleted_accounts(&mut self.connection(), retention_days)
+    }
+
+    fn dialect(&self) -> Dialect {
+        Dialect::Sqlite
+    }
+
+    // Each migration commits along with its record, so a concurrent run fails and rolls back
+    // rather than applying a migration twice.
+    fn lock_migrations(&self) -> Result<(), ActixError> {
+        Ok(())
+    }
+
+    fn unlock_migrations(&self) -> Result<(), ActixError> {
+        Ok(())
+    }
+
+    fn create_migrations_table(&self) -> Result<(), ActixError> {
+        migrations::create_migrations_table(&self.connection())
+    }
+
+    fn has_table(&self, table: &str) -> Result<bool, ActixError> {
+        migrations::has_table(&self.connection(), table)
+    }
+
+    fn get_applied_migrations(&self) -> Result<Vec<(u32, String)>, ActixError> {
+        migrations::get_applied_migrations(&self.connection())
+    }
+
+    fn apply_migration(&self, migration: &Migration) -> Result<(), ActixError> {
+        migrations::apply_migration(&mut self.connection(), migration)
+    }
+
+    fn revert_migration(&self, migration: &Migration) -> Result<(), ActixError> {
+        migrations::revert_migration(&mut self.connection(), migration)
+    }
+
+    fn mark_migration_applied(&self, migration: &Migration) -> Result<(), ActixError> {
+        migrations::mark_migration_applied(&self.connection(), migration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::{run, MigrateArgs, MigrateCommand, MIGRATIONS};
+    use crate::persistance::suite;
+
+    fn migrated() -> SqliteDatabase {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        run(&db, &MigrateArgs { command: MigrateCommand::Up, dry_run: false }).unwrap();
+        db
+    }
+
+    #[test]
+    fn migrations_apply_and_revert() {
+        let db = migrated();
+        assert_eq!(db.get_applied_migrations().unwrap().len(), MIGRATIONS.len());
+
+        run(&db, &MigrateArgs { command: MigrateCommand::Down(MIGRATIONS.len()), dry_run: false }).unwrap();
+        assert!(db.get_applied_migrations().unwrap().is_empty());
+        let tables: i64 = db.connection()
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'accounts'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tables, 0);
+    }
+
+    #[test]
+    fn hand_made_schemas_must_be_baselined() {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        for migration in &MIGRATIONS[..2] {
+            db.connection().execute_batch(migration.sqlite.up).unwrap();
+        }
+
+        let up = MigrateArgs { command: MigrateCommand::Up, dry_run: false };
+        assert!(run(&db, &up).unwrap_err().contains("migrate baseline 2"));
+        run(&db, &MigrateArgs { command: MigrateCommand::Baseline(2), dry_run: false }).unwrap();
+        run(&db, &up).unwrap();
+        assert_eq!(db.get_applied_migrations().unwrap().len(), MIGRATIONS.len());
+    }
+
+    #[test]
+    fn behaves_like_the_other_backends() {
+        for case in suite::CASES {
+            case(&migrated());
+        }
+
+        let db = migrated();
+        suite::purged_accounts_take_their_login_attempts_along(&db, || {
+            db.connection().execute("UPDATE accounts SET deleted_at = datetime('now', '-40 days') WHERE deleted_at IS NOT NULL", []).unwrap();
+        });
+    }
+}
@@ -0,0 +1,52 @@
+use crate::models::Account;
+use crate::errors::ActixError;
+use crate::persistance::PASSWORD_RESET_LIFETIME_MINUTES;
+use super::accounts::update_account_password_data;
+use rusqlite::{named_params, Connection, OptionalExtension};
+
+pub fn create_password_reset(conn: &Connection, id_account: u64, token_hash: &str) -> Result<(), ActixError> {
+    conn.execute(
+        "INSERT INTO password_reset (id_account,token_hash,expires_at) VALUES (:id_account,:token_hash,datetime('now', :lifetime))",
+        named_params! {
+            ":id_account": id_account,
+            ":token_hash": token_hash,
+            ":lifetime": format!("+{} minutes", PASSWORD_RESET_LIFETIME_MINUTES),
+        },
+    )?;
+
+    Ok(())
+}
+
+pub fn get_password_reset(conn: &Connection, token_hash: &str) -> Result<u64, ActixError> {
+    select_valid_password_reset(conn, token_hash)?.ok_or(ActixError::UnAuthorized)
+}
+
+// Marks the token as used and stores the new password of its account; every other pending token
+// of the account is burnt too.
+pub fn reset_password(conn: &mut Connection, token_hash: &str, hash_password: &str) -> Result<Account, ActixError> {
+    let tx = conn.transaction()?;
+
+    let id_account = select_valid_password_reset(&tx, token_hash)?.ok_or(ActixError::UnAuthorized)?;
+    tx.execute(
+        "UPDATE password_reset SET used_at = CURRENT_TIMESTAMP WHERE id_account = :id_account AND used_at IS NULL",
+        named_params! {
+            ":id_account": id_account,
+        },
+    )?;
+    let account = update_account_password_data(&tx, id_account, hash_password)?;
+    tx.commit()?;
+
+    Ok(account)
+}
+
+fn select_valid_password_reset(conn: &Connection, token_hash: &str) -> rusqlite::Result<Option<u64>> {
+    conn.query_row(
+        "SELECT id_account FROM password_reset
+         WHERE token_hash = :token_hash AND used_at IS NULL AND expires_at > datetime('now')",
+        named_params! {
+            ":token_hash": token_hash,
+        },
+        |row| row.get(0),
+    )
+    .optional()
+}
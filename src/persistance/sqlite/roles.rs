@@ -0,0 +1,52 @@
+use crate::errors::ActixError;
+use rusqlite::{named_params, Connection};
+
+pub fn get_account_authorizations(
+    conn: &Connection,
+    id_account: u64,
+) -> Result<(Vec<String>, Vec<String>), ActixError> {
+    let roles = select_names(
+        conn,
+        "SELECT r.name FROM roles r
+         JOIN account_roles ar ON ar.id_role = r.id
+         WHERE ar.id_account = :id_account ORDER BY r.name ASC",
+        id_account,
+    )?;
+    let permissions = select_names(
+        conn,
+        "SELECT DISTINCT p.name FROM permissions p
+         JOIN role_permissions rp ON rp.id_permission = p.id
+         JOIN account_roles ar ON ar.id_role = rp.id_role
+         WHERE ar.id_account = :id_account ORDER BY p.name ASC",
+        id_account,
+    )?;
+
+    Ok((roles, permissions))
+}
+
+pub fn insert_account_role(
+    conn: &Connection,
+    id_account: u64,
+    role: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO account_roles (id_account,id_role) SELECT :id_account, id FROM roles WHERE name = :role",
+        named_params! {
+            ":id_account": id_account,
+            ":role": role,
+        },
+    )
+    .map(|_| ())
+}
+
+fn select_names(conn: &Connection, query: &str, id_account: u64) -> rusqlite::Result<Vec<String>> {
+    let mut statement = conn.prepare(query)?;
+    let names = statement.query_map(
+        named_params! {
+            ":id_account": id_account,
+        },
+        |row| row.get(0),
+    )?;
+
+    names.collect()
+}
@@ -0,0 +1,108 @@
+use crate::errors::ActixError;
+use crate::models::AccountTotp;
+use crate::password::hash_token;
+use crate::persistance::{second_factor, SecondFactor};
+use rusqlite::{named_params, Connection, OptionalExtension};
+
+pub fn get_totp_data(conn: &Connection, id_account: u64) -> Result<Option<AccountTotp>, ActixError> {
+    Ok(conn.query_row(
+        "SELECT id_account, secret, enabled FROM account_totp WHERE id_account = :id_account",
+        named_params! {
+            ":id_account": id_account,
+        },
+        |row| Ok(AccountTotp {
+            id_account: row.get(0)?,
+            secret: row.get(1)?,
+            enabled: row.get(2)?,
+        }),
+    )
+    .optional()?)
+}
+
+pub fn save_pending_totp(conn: &Connection, id_account: u64, secret: &str) -> Result<(), ActixError> {
+    conn.execute(
+        "INSERT INTO account_totp (id_account,secret) VALUES (:id_account,:secret)
+         ON CONFLICT (id_account) DO UPDATE SET secret = :secret, enabled = 0, last_used_step = 0",
+        named_params! {
+            ":id_account": id_account,
+            ":secret": secret,
+        },
+    )?;
+
+    Ok(())
+}
+
+pub fn enable_totp(conn: &mut Connection, id_account: u64, recovery_codes: &[String]) -> Result<(), ActixError> {
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "UPDATE account_totp SET enabled = 1 WHERE id_account = :id_account",
+        named_params! {
+            ":id_account": id_account,
+        },
+    )?;
+    delete_recovery_codes(&tx, id_account)?;
+    {
+        let mut insert = tx.prepare("INSERT INTO recovery_codes (id_account,code_hash) VALUES (:id_account,:code_hash)")?;
+        for code in recovery_codes {
+            insert.execute(named_params! {
+                ":id_account": id_account,
+                ":code_hash": hash_token(code),
+            })?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+pub fn disable_totp(conn: &mut Connection, id_account: u64) -> Result<(), ActixError> {
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "DELETE FROM account_totp WHERE id_account = :id_account",
+        named_params! {
+            ":id_account": id_account,
+        },
+    )?;
+    delete_recovery_codes(&tx, id_account)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+// Accepts either a TOTP code or an unused recovery code. Both are burnt once accepted.
+pub fn verify_second_factor(conn: &Connection, account_totp: &AccountTotp, code: &str) -> Result<(), ActixError> {
+    let accepted = match second_factor(account_totp, code) {
+        SecondFactor::TotpStep(step) => conn.execute(
+            "UPDATE account_totp SET last_used_step = :step WHERE id_account = :id_account AND last_used_step < :step",
+            named_params! {
+                ":id_account": account_totp.id_account,
+                ":step": step,
+            },
+        )? > 0,
+        SecondFactor::RecoveryCode(code_hash) => conn.execute(
+            "UPDATE recovery_codes SET used_at = CURRENT_TIMESTAMP WHERE id_account = :id_account AND code_hash = :code_hash AND used_at IS NULL",
+            named_params! {
+                ":id_account": account_totp.id_account,
+                ":code_hash": code_hash,
+            },
+        )? > 0,
+        SecondFactor::Invalid => false,
+    };
+
+    if accepted {
+        Ok(())
+    } else {
+        Err(ActixError::UnAuthorized)
+    }
+}
+
+fn delete_recovery_codes(conn: &Connection, id_account: u64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM recovery_codes WHERE id_account = :id_account",
+        named_params! {
+            ":id_account": id_account,
+        },
+    )
+}
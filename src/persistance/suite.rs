@@ -0,0 +1,134 @@
+// Behaviour every backend must share. Each case expects a freshly migrated database: SQLite runs
+// them on in-memory databases, MySQL and PostgreSQL on the database of `MYSQL_URL` and
+// `POSTGRES_URL`, migrated again for every case.
+use crate::accounts::AccountChange;
+use crate::errors::ActixError;
+use crate::totp;
+use super::Database;
+
+type Case = fn(&dyn Database);
+
+// A failing case is named by the location of its assertion.
+pub const CASES: &[Case] = &[
+    usernames_are_unique_among_active_accounts,
+    updates_leave_the_other_columns_alone,
+    token_pairs_are_revoked_once,
+    password_reset_tokens_are_single_use,
+    failed_password_resets_leave_the_token_usable,
+    second_factors_are_single_use,
+    login_attempts_are_saved_and_deleted,
+];
+pub fn usernames_are_unique_among_active_accounts(db: &dyn Database) {
+    let alice = db.create("alice", "hash").unwrap();
+    let bob = db.create("bob", "hash").unwrap();
+    assert!(matches!(db.create("alice", "hash"), Err(ActixError::SameAccountName)));
+    assert!(matches!(db.update_username(bob.id, "alice"), Err(ActixError::SameAccountName)));
+    assert_eq!(db.list(0, 10).unwrap().len(), 2);
+
+    let since = db.now().unwrap();
+    db.delete(alice.id).unwrap();
+    assert!(matches!(db.get(alice.id), Err(ActixError::NotFound)));
+    assert!(matches!(db.update_username(alice.id, "alicia"), Err(ActixError::NotFound)));
+    assert!(matches!(db.update_password(alice.id, "rehashed"), Err(ActixError::NotFound)));
+    let (changes, _) = db.changes_since(since).unwrap();
+    assert!(changes.iter().any(|change| matches!(change, AccountChange::Deleted(id) if *id == alice.id)));
+
+    let alice_again = db.create("alice", "hash").unwrap();
+    assert_eq!(db.get_by_username("alice").unwrap().id, alice_again.id);
+    assert!(matches!(db.restore(alice.id), Err(ActixError::SameAccountName)));
+}
+
+pub fn updates_leave_the_other_columns_alone(db: &dyn Database) {
+    let account = db.create("alice", "hash").unwrap();
+
+    db.update_username(account.id, "alicia").unwrap();
+    let updated = db.update_password(account.id, "rehashed").unwrap();
+    assert_eq!((updated.username.as_str(), updated.password.as_str()), ("alicia", "rehashed"));
+    assert_eq!(db.get(account.id).unwrap().username, "alicia");
+}
+
+pub fn token_pairs_are_revoked_once(db: &dyn Database) {
+    let account = db.create("alice", "hash").unwrap();
+    let first = db.insert_access_token(account.id, "access_1", "refresh_1", "family").unwrap();
+    db.insert_access_token(account.id, "access_2", "refresh_2", "family").unwrap();
+
+    assert_eq!(db.get_refresh_token("refresh_1").unwrap(), Some((first.id, account.id, String::from("family"), false)));
+    assert!(db.revoke_token_pair(first.id).unwrap());
+    assert!(!db.revoke_token_pair(first.id).unwrap());
+    assert_eq!(db.get_refresh_token("refresh_1").unwrap().map(|(_, _, _, revoked)| revoked), Some(true));
+    assert_eq!(db.get_refresh_token("unknown").unwrap(), None);
+
+    db.revoke_token_family("family").unwrap();
+    let revoked: Vec<String> = db.get_revoked_access_tokens().unwrap().into_iter().map(|(token, _)| token).collect();
+    assert_eq!(revoked.len(), 2);
+    assert!(revoked.contains(&String::from("access_2")));
+}
+
+pub fn password_reset_tokens_are_single_use(db: &dyn Database) {
+    let account = db.create("alice", "hash").unwrap();
+
+    db.create_password_reset(account.id, "first").unwrap();
+    db.create_password_reset(account.id, "second").unwrap();
+    assert_eq!(db.get_password_reset("first").unwrap(), account.id);
+    assert_eq!(db.reset_password("first", "rehashed").unwrap().password, "rehashed");
+    assert!(db.get_password_reset("first").is_err());
+    assert!(db.reset_password("first", "hash").is_err());
+    assert!(db.reset_password("second", "hash").is_err());
+    assert_eq!(db.get(account.id).unwrap().password, "rehashed");
+}
+
+pub fn failed_password_resets_leave_the_token_usable(db: &dyn Database) {
+    let account = db.create("alice", "hash").unwrap();
+    db.create_password_reset(account.id, "token").unwrap();
+
+    db.delete(account.id).unwrap();
+    assert!(matches!(db.reset_password("token", "rehashed"), Err(ActixError::NotFound)));
+    db.restore(account.id).unwrap();
+    assert_eq!(db.reset_password("token", "rehashed").unwrap().id, account.id);
+}
+
+pub fn second_factors_are_single_use(db: &dyn Database) {
+    let account = db.create("alice", "hash").unwrap();
+    let secret = totp::generate_secret();
+    db.save_pending_totp(account.id, &secret).unwrap();
+    db.enable_totp(account.id, &[String::from("recovery-code")]).unwrap();
+    let account_totp = db.get_totp(account.id).unwrap().unwrap();
+
+    let code = totp::code_at(&secret, db.now().unwrap() as u64);
+    db.verify_second_factor(&account_totp, &code).unwrap();
+    assert!(matches!(db.verify_second_factor(&account_totp, &code), Err(ActixError::UnAuthorized)));
+
+    db.verify_second_factor(&account_totp, "recovery-code").unwrap();
+    assert!(matches!(db.verify_second_factor(&account_totp, "recovery-code"), Err(ActixError::UnAuthorized)));
+    assert!(matches!(db.verify_second_factor(&account_totp, "other-code"), Err(ActixError::UnAuthorized)));
+}
+
+pub fn login_attempts_are_saved_and_deleted(db: &dyn Database) {
+    let now = db.now().unwrap();
+
+    db.save_login_attempt(&(String::from("account:alice"), 1, 0, now)).unwrap();
+    db.save_login_attempt(&(String::from("account:alice"), 2, now + 60, now)).unwrap();
+    assert_eq!(db.get_login_attempts(900).unwrap(), vec![(String::from("account:alice"), 2, now + 60, now)]);
+
+    db.delete_login_attempt("account:alice").unwrap();
+    assert!(db.get_login_attempts(900).unwrap().is_empty());
+}
+
+// `age_deletions` moves every deletion 40 days back, which only the backend itself can do.
+pub fn purged_accounts_take_their_login_attempts_along(db: &dyn Database, age_deletions: impl Fn()) {
+    let now = db.now().unwrap();
+    for username in ["alice", "bob"] {
+        let account = db.create(username, "hash").unwrap();
+        db.save_login_attempt(&(format!("account:{}", username), 1, now, now)).unwrap();
+        db.delete(account.id).unwrap();
+    }
+    db.save_login_attempt(&(String::from("ip:10.0.0.1"), 1, now, now)).unwrap();
+    db.create("bob", "hash").unwrap();
+    age_deletions();
+
+    assert_eq!(db.purge_deleted_accounts(30).unwrap(), 2);
+    let keys: Vec<String> = db.get_login_attempts(900).unwrap().into_iter().map(|(key, ..)| key).collect();
+    assert_eq!(keys.len(), 2);
+    assert!(keys.contains(&String::from("account:bob")));
+    assert!(keys.contains(&String::from("ip:10.0.0.1")));
+}
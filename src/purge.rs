@@ -0,0 +1,30 @@
+use std::env;
+use std::time::Duration;
+use actix_web::{rt, web};
+use crate::persistance::Database;
+
+// `ACCOUNT_RETENTION_DAYS`, 30 by default.
+pub fn retention_days_from_env() -> Result<u32, String> {
+  match env::var("ACCOUNT_RETENTION_DAYS") {
+    Ok(days) => days.parse().map_err(|_| format!("ACCOUNT_RETENTION_DAYS doit être un nombre de jours, pas {}", days)),
+    Err(_) => Ok(30),
+  }
+}
+
+// Hard-deletes accounts that have stayed soft-deleted for longer than `retention_days`.
+pub fn spawn_account_purge(db: web::Data<dyn Database>, retention_days: u32, period: Duration) {
+  rt::spawn(async move {
+    let mut interval = rt::time::interval(period);
+
+    loop {
+      interval.tick().await;
+      let db = db.clone();
+      match web::block(move || db.purge_deleted_accounts(retention_days)).await {
+        Ok(Ok(0)) => {}
+        Ok(Ok(purged)) => log::info!("{} compte(s) supprimé(s) définitivement", purged),
+        Ok(Err(err)) => eprintln!("Erreur lors de la purge des comptes: {}", err),
+        Err(err) => eprintln!("Erreur lors de la purge des comptes: {}", err),
+      }
+    }
+  });
+}
@@ -0,0 +1,333 @@
+use std::{
+  env,
+  future::{ready, Ready},
+  rc::Rc,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  time::Duration,
+};
+
+use actix_web::{
+  body::EitherBody,
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  http::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
+  web, Error,
+};
+use futures_util::{future::LocalBoxFuture, FutureExt, TryFutureExt};
+use crate::client_ip::TrustedProxies;
+use crate::errors::ActixError;
+use crate::jwt_check::bearer_token;
+use crate::tokens::TokenKeys;
+
+mod policy;
+mod store;
+
+pub use policy::{Quota, RateLimitPolicies};
+pub use store::{MemoryStore, RateLimitStore, RedisStore};
+
+#[doc(hidden)]
+pub struct RateLimitService<S> {
+  service: Rc<S>,
+  limiter: RateLimit,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitService<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = Error;
+  type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+      let (policy, quota) = self.limiter.policies.resolve(req.method(), req.path());
+      let Some(quota) = quota else {
+          return self.service
+              .call(req)
+              .map_ok(ServiceResponse::map_into_left_body)
+              .boxed_local();
+      };
+
+      let key = format!("{}:{}", policy, self.limiter.client_key(&req));
+      let limiter = self.limiter.clone();
+      let service = self.service.clone();
+
+      Box::pin(async move {
+          let decision = limiter.allow_query(key, quota).await;
+
+          if !decision.allowed {
+              let mut res = req.error_response(ActixError::TooManyRequests);
+              decision.insert_headers(res.headers_mut());
+              res.headers_mut().insert(RETRY_AFTER, HeaderValue::from(decision.retry_after));
+              return Ok(res.map_into_right_body());
+          }
+
+          let mut res = service.call(req).await?;
+          decision.insert_headers(res.headers_mut());
+          Ok(res.map_into_left_body())
+      })
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimitDecision {
+  pub allowed: bool,
+  pub limit: u64,
+  pub remaining: u64,
+  // Seconds until the bucket is full again.
+  pub reset: u64,
+  // Seconds until the next request can be accepted.
+  pub retry_after: u64,
+}
+
+impl RateLimitDecision {
+  // `wait` is how far ahead of now the theoretical arrival time would be once the request is
+  // counted; the request is accepted when that stays within the quota's tolerance.
+  fn from_gcra(quota: Quota, wait: Duration) -> Self {
+      let interval = quota.emission_interval();
+      let tolerance = quota.tolerance();
+
+      if wait > tolerance {
+          RateLimitDecision {
+              allowed: false,
+              limit: quota.burst as u64,
+              remaining: 0,
+              reset: ceil_seconds(wait.saturating_sub(interval)),
+              retry_after: ceil_seconds(wait - tolerance),
+          }
+      } else {
+          RateLimitDecision {
+              allowed: true,
+              limit: quota.burst as u64,
+              remaining: ((tolerance - wait).as_nanos() / interval.as_nanos().max(1)) as u64,
+              reset: ceil_seconds(wait),
+              retry_after: 0,
+          }
+      }
+  }
+
+  fn insert_headers(&self, headers: &mut HeaderMap) {
+      headers.insert(HeaderName::from_static("x-ratelimit-limit"), HeaderValue::from(self.limit));
+      headers.insert(HeaderName::from_static("x-ratelimit-remaining"), HeaderValue::from(self.remaining));
+      headers.insert(HeaderName::from_static("x-ratelimit-reset"), HeaderValue::from(self.reset));
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RateLimitKey {
+  Ip,
+  // Authenticated requests are limited per account, anonymous ones per IP.
+  Account,
+}
+
+impl RateLimitKey {
+  // `RATE_LIMIT_KEY` is either `ip` (default) or `account`.
+  pub fn from_env() -> Result<Self, String> {
+    match env::var("RATE_LIMIT_KEY").as_deref() {
+      Ok("account") => Ok(RateLimitKey::Account),
+      Ok("ip") | Err(_) => Ok(RateLimitKey::Ip),
+      Ok(other) => Err(format!("RATE_LIMIT_KEY doit valoir ip ou account, pas {}", other)),
+    }
+  }
+}
+
+// Clones share their buckets, so a single `RateLimit` built before `HttpServer::new` limits
+// across every worker. With a shared store, instances limit together; while the store is
+// unreachable each instance falls back to limiting on its own.
+#[derive(Clone)]
+pub struct RateLimit {
+  policies: Arc<RateLimitPolicies>,
+  key: RateLimitKey,
+  // Only needed to read the account of `RateLimitKey::Account` requests.
+  keys: TokenKeys,
+  trusted_proxies: TrustedProxies,
+  local: Arc<MemoryStore>,
+  shared: Option<Arc<dyn RateLimitStore>>,
+  shared_unavailable: Arc<AtomicBool>,
+}
+
+impl RateLimit {
+  pub fn new(policies: RateLimitPolicies, key: RateLimitKey, keys: TokenKeys, trusted_proxies: TrustedProxies) -> Self {
+      RateLimit::with_local_store(policies, key, keys, trusted_proxies, MemoryStore::new())
+  }
+
+  pub fn with_local_store(policies: RateLimitPolicies, key: RateLimitKey, keys: TokenKeys, trusted_proxies: TrustedProxies, local: MemoryStore) -> Self {
+      Self {
+          policies: Arc::new(policies),
+          key,
+          keys,
+          trusted_proxies,
+          local: Arc::new(local),
+          shared: None,
+          shared_unavailable: Arc::new(AtomicBool::new(false)),
+      }
+  }
+
+  pub fn with_shared_store(mut self, store: Arc<dyn RateLimitStore>) -> Self {
+      self.shared = Some(store);
+      self
+  }
+
+  // `RATE_LIMIT_REDIS_URL` enables the Redis store.
+  pub fn shared_store_from_env(self) -> Self {
+      match env::var("RATE_LIMIT_REDIS_URL") {
+          Ok(url) => {
+              let store = RedisStore::new(&url).unwrap_or_else(|err| {
+                  eprintln!("Erreur lors de la configuration du store de limitation: {}", err);
+                  std::process::exit(1);
+              });
+              self.with_shared_store(Arc::new(store))
+          },
+          Err(_) => self,
+      }
+  }
+
+  fn client_key(&self, req: &ServiceRequest) -> String {
+      if self.key == RateLimitKey::Account {
+          if let Some(claims) = bearer_token(req.headers()).and_then(|token| self.keys.decode_access_token(token).ok()) {
+              return format!("account:{}", claims.id_account);
+          }
+      }
+
+      match self.trusted_proxies.client_ip(req.peer_addr(), req.headers()) {
+          Some(ip) => format!("ip:{}", ip),
+          None => String::from("ip:unknown"),
+      }
+  }
+
+  async fn allow_query(&self, key: String, quota: Quota) -> RateLimitDecision {
+      let Some(shared) = self.shared.clone() else {
+          return self.local.allow_query(&key, quota);
+      };
+
+      let local_key = key.clone();
+      let result = web::block(move || shared.allow_query(&key, quota))
+          .await
+          .unwrap_or_else(|err| Err(err.to_string()));
+
+      match result {
+          Ok(decision) => {
+              if self.shared_unavailable.swap(false, Ordering::Relaxed) {
+                  log::info!("Store de limitation de nouveau disponible");
+              }
+              decision
+          },
+          Err(err) => {
+              if !self.shared_unavailable.swap(true, Ordering::Relaxed) {
+                  eprintln!("Store de limitation indisponible, limitation locale: {}", err);
+              }
+              self.local.allow_query(&local_key, quota)
+          },
+      }
+  }
+}
+
+fn ceil_seconds(duration: Duration) -> u64 {
+  duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = Error;
+  type Transform = RateLimitService<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+      ready(Ok(RateLimitService {
+          service: Rc::new(service),
+          limiter: self.clone(),
+      }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use actix_web::{http::StatusCode, test, App, HttpResponse};
+  use crate::models::BasicResponseError;
+
+  struct UnreachableStore;
+
+  impl RateLimitStore for UnreachableStore {
+      fn allow_query(&self, _key: &str, _quota: Quota) -> Result<RateLimitDecision, String> {
+          Err(String::from("connection refused"))
+      }
+  }
+
+  fn keys() -> TokenKeys {
+      TokenKeys::new("token_secret", "refresh_token_secret")
+  }
+
+  async fn statuses(limiter: RateLimit, requests: usize) -> Vec<StatusCode> {
+      let app = test::init_service(
+          App::new()
+              .wrap(limiter)
+              .route("/", web::get().to(HttpResponse::Ok)),
+      ).await;
+
+      let mut statuses = Vec::new();
+      for _ in 0..requests {
+          let req = test::TestRequest::get().uri("/").peer_addr("10.0.0.1:1234".parse().unwrap()).to_request();
+          statuses.push(test::call_service(&app, req).await.status());
+      }
+      statuses
+  }
+
+  #[actix_web::test]
+  async fn responses_report_the_quota_left() {
+      let limiter = RateLimit::new(RateLimitPolicies::new(Quota::per_minute(2, 2)), RateLimitKey::Ip, keys(), TrustedProxies::default());
+      let app = test::init_service(
+          App::new()
+              .wrap(limiter)
+              .route("/", web::get().to(HttpResponse::Ok)),
+      ).await;
+      let request = || test::TestRequest::get().uri("/").peer_addr("10.0.0.1:1234".parse().unwrap()).to_request();
+      let header = |res: &ServiceResponse<_>, name: &str| res.headers().get(name).map(|value| value.to_str().unwrap().to_string());
+
+      let allowed = test::call_service(&app, request()).await;
+      assert_eq!(header(&allowed, "X-RateLimit-Limit").as_deref(), Some("2"));
+      assert_eq!(header(&allowed, "X-RateLimit-Remaining").as_deref(), Some("1"));
+      assert_eq!(header(&allowed, "X-RateLimit-Reset").as_deref(), Some("30"));
+      assert_eq!(header(&allowed, "Retry-After"), None);
+
+      test::call_service(&app, request()).await;
+      let denied = test::call_service(&app, request()).await;
+      assert_eq!(denied.status(), StatusCode::TOO_MANY_REQUESTS);
+      assert_eq!(header(&denied, "X-RateLimit-Remaining").as_deref(), Some("0"));
+      assert_eq!(header(&denied, "Retry-After").as_deref(), Some("30"));
+      let body: BasicResponseError = test::read_body_json(denied).await;
+      assert_eq!((body.error.as_str(), body.code), ("Too many requests.", 429));
+  }
+
+  #[actix_web::test]
+  async fn falls_back_to_local_limiting_when_the_store_is_unreachable() {
+      let limiter = RateLimit::new(RateLimitPolicies::new(Quota::per_minute(2, 2)), RateLimitKey::Ip, keys(), TrustedProxies::default())
+          .with_shared_store(Arc::new(UnreachableStore));
+
+      assert_eq!(statuses(limiter.clone(), 3).await, vec![StatusCode::OK, StatusCode::OK, StatusCode::TOO_MANY_REQUESTS]);
+      assert!(limiter.shared_unavailable.load(Ordering::Relaxed));
+  }
+
+  #[actix_web::test]
+  async fn uses_the_shared_store_when_available() {
+      let shared = Arc::new(MemoryStore::new());
+      let limiter = RateLimit::new(RateLimitPolicies::new(Quota::per_minute(2, 2)), RateLimitKey::Ip, keys(), TrustedProxies::default())
+          .with_shared_store(shared.clone());
+
+      assert_eq!(statuses(limiter.clone(), 3).await, vec![StatusCode::OK, StatusCode::OK, StatusCode::TOO_MANY_REQUESTS]);
+      assert_eq!(shared.len(), 1);
+      assert_eq!(limiter.local.len(), 0);
+  }
+}
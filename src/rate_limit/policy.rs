@@ -0,0 +1,250 @@
+use std::{collections::HashMap, env, fs, time::Duration};
+
+use actix_web::{dev::ResourceDef, http::Method};
+use serde::Deserialize;
+
+pub const DEFAULT_POLICY: &str = "default";
+
+// `rate` requests are replenished every `period`, and up to `burst` can be sent back to back.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quota {
+  pub rate: u32,
+  pub period: Duration,
+  pub burst: u32,
+}
+
+impl Quota {
+  pub fn per_second(rate: u32, burst: u32) -> Self {
+      Quota { rate, period: Duration::from_secs(1), burst }
+  }
+
+  pub fn per_minute(rate: u32, burst: u32) -> Self {
+      Quota { rate, period: Duration::from_secs(60), burst }
+  }
+
+  // `RATE_LIMIT_PER_SECOND` (default 1) and `RATE_LIMIT_BURST` (default 10).
+  pub fn from_env() -> Result<Self, String> {
+      let read = |name: &str, default: u32| match env::var(name) {
+          Ok(value) => value.parse().map_err(|_| format!("{} must be a number, not {}", name, value)),
+          Err(_) => Ok(default),
+      };
+
+      Quota::per_second(read("RATE_LIMIT_PER_SECOND", 1)?, read("RATE_LIMIT_BURST", 10)?).validate()
+  }
+
+  // A zero rate, period or burst would let nothing through, or everything.
+  fn validate(self) -> Result<Self, String> {
+      if self.rate == 0 || self.period.is_zero() || self.burst == 0 {
+          return Err(format!("A rate limit quota needs a rate, period and burst above zero: {:?}", self));
+      }
+      Ok(self)
+  }
+
+  pub fn emission_interval(&self) -> Duration {
+      self.period / self.rate.max(1)
+  }
+
+  pub fn tolerance(&self) -> Duration {
+      self.emission_interval() * self.burst.max(1)
+  }
+}
+
+struct Rule {
+  pattern: ResourceDef,
+  methods: Vec<Method>,
+  policy: String,
+}
+
+// Named quotas and the scopes or routes they apply to. The first matching rule wins, requests
+// matching none fall back to the `default` policy. A policy without quota is exempt.
+pub struct RateLimitPolicies {
+  policies: HashMap<String, Option<Quota>>,
+  rules: Vec<Rule>,
+}
+
+impl RateLimitPolicies {
+  pub fn new(default: Quota) -> Self {
+      let mut policies = HashMap::new();
+      policies.insert(DEFAULT_POLICY.to_string(), Some(default));
+
+      RateLimitPolicies { policies, rules: Vec::new() }
+  }
+
+  pub fn with_policy(mut self, name: &str, quota: Option<Quota>) -> Self {
+      self.policies.insert(name.to_string(), quota);
+      self
+  }
+
+  pub fn with_scope(self, prefix: &str, methods: &[Method], policy: &str) -> Self {
+      self.with_rule(ResourceDef::prefix(prefix), methods, policy)
+  }
+
+  pub fn with_route(self, path: &str, methods: &[Method], policy: &str) -> Self {
+      self.with_rule(ResourceDef::new(path), methods, policy)
+  }
+
+  fn with_rule(mut self, pattern: ResourceDef, methods: &[Method], policy: &str) -> Self {
+      if !self.policies.contains_key(policy) {
+          panic!("Unknown rate limit policy: {}", policy);
+      }
+
+      self.rules.push(Rule { pattern, methods: methods.to_vec(), policy: policy.to_string() });
+      self
+  }
+
+  pub fn builtin(default: Quota) -> Self {
+      RateLimitPolicies::new(default)
+          .with_policy("strict", Some(Quota::per_minute(10, 5)))
+          .with_policy("lenient", Some(Quota::per_second(5, 50)))
+          .with_policy("exempt", None)
+          .with_route("/", &[Method::GET], "exempt")
+          .with_route("/login", &[Method::POST], "strict")
+          .with_route("/login/2fa", &[Method::POST], "strict")
+          .with_route("/password/forgot", &[Method::POST], "strict")
+          .with_route("/password/reset", &[Method::POST], "strict")
+          .with_route("/v1/token/refresh", &[Method::POST], "strict")
+          .with_route("/v1/accounts", &[Method::POST], "strict")
+          .with_scope("/v1/accounts", &[Method::GET], "lenient")
+  }
+
+  // The built-in policies, with the default quota read from the environment. `RATE_LIMIT_CONFIG`
+  // may point to a JSON file redefining policies by name and replacing the rules, e.g.
+  // `{"policies": {"strict": {"rate": 3, "period_seconds": 60, "burst": 3}, "exempt": null},
+  //   "rules": [{"route": "/login", "methods": ["POST"], "policy": "strict"}]}`.
+  pub fn from_env() -> Result<Self, String> {
+      let policies = RateLimitPolicies::builtin(Quota::from_env()?);
+
+      match env::var("RATE_LIMIT_CONFIG") {
+          Ok(path) => {
+              let content = fs::read_to_string(&path)
+                  .map_err(|err| format!("Cannot read RATE_LIMIT_CONFIG {}: {}", path, err))?;
+              let config: PoliciesConfig = serde_json::from_str(&content)
+                  .map_err(|err| format!("Invalid RATE_LIMIT_CONFIG {}: {}", path, err))?;
+              policies.apply(config).map_err(|err| format!("Invalid RATE_LIMIT_CONFIG {}: {}", path, err))
+          },
+          Err(_) => Ok(policies),
+      }
+  }
+
+  fn apply(mut self, config: PoliciesConfig) -> Result<Self, String> {
+      for (name, quota) in config.policies {
+          let quota = quota.map(QuotaConfig::into_quota).transpose()?;
+          self = self.with_policy(&name, quota);
+      }
+
+      let Some(rules) = config.rules else {
+          return Ok(self);
+      };
+
+      self.rules.clear();
+      for rule in rules {
+          if !self.policies.contains_key(&rule.policy) {
+              return Err(format!("Unknown rate limit policy: {}", rule.policy));
+          }
+
+          let methods = rule.methods.iter()
+              .map(|method| method.parse().map_err(|_| format!("Invalid method in rate limit rule: {}", method)))
+              .collect::<Result<Vec<Method>, String>>()?;
+
+          self = match (rule.scope, rule.route) {
+              (Some(prefix), None) => self.with_scope(&prefix, &methods, &rule.policy),
+              (None, Some(path)) => self.with_route(&path, &methods, &rule.policy),
+              _ => return Err(String::from("A rate limit rule needs exactly one of scope or route")),
+          };
+      }
+      Ok(self)
+  }
+
+  // Returns the name of the policy applying to the request and its quota, `None` when exempt.
+  pub fn resolve(&self, method: &Method, path: &str) -> (&str, Option<Quota>) {
+      let policy = self.rules.iter()
+          .find(|rule| (rule.methods.is_empty() || rule.methods.contains(method)) && rule.pattern.is_match(path))
+          .map(|rule| rule.policy.as_str())
+          .unwrap_or(DEFAULT_POLICY);
+
+      (policy, self.policies.get(policy).copied().flatten())
+  }
+}
+
+#[derive(Deserialize)]
+struct PoliciesConfig {
+  #[serde(default)]
+  policies: HashMap<String, Option<QuotaConfig>>,
+  rules: Option<Vec<RuleConfig>>,
+}
+
+#[derive(Deserialize)]
+struct QuotaConfig {
+  rate: u32,
+  #[serde(default = "default_period_seconds")]
+  period_seconds: u64,
+  burst: u32,
+}
+
+impl QuotaConfig {
+  fn into_quota(self) -> Result<Quota, String> {
+      Quota { rate: self.rate, period: Duration::from_secs(self.period_seconds), burst: self.burst }.validate()
+  }
+}
+
+fn default_period_seconds() -> u64 {
+  1
+}
+
+#[derive(Deserialize)]
+struct RuleConfig {
+  scope: Option<String>,
+  route: Option<String>,
+  #[serde(default)]
+  methods: Vec<String>,
+  policy: String,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn builtin_rules_pick_the_first_matching_policy() {
+      let policies = RateLimitPolicies::builtin(Quota::per_second(1, 10));
+
+      assert_eq!(policies.resolve(&Method::GET, "/"), ("exempt", None));
+      assert_eq!(policies.resolve(&Method::POST, "/login").0, "strict");
+      assert_eq!(policies.resolve(&Method::POST, "/v1/accounts").0, "strict");
+      assert_eq!(policies.resolve(&Method::POST, "/v1/token/refresh").0, "strict");
+      assert_eq!(policies.resolve(&Method::GET, "/v1/accounts/3").0, "lenient");
+      assert_eq!(policies.resolve(&Method::PUT, "/v1/accounts/3"), ("default", Some(Quota::per_second(1, 10))));
+  }
+
+  #[test]
+  fn config_redefines_policies_and_replaces_rules() {
+      let config = serde_json::from_str(r#"{
+          "policies": {"strict": {"rate": 2, "period_seconds": 60, "burst": 2}, "exempt": null},
+          "rules": [{"scope": "/v1", "policy": "exempt"}, {"route": "/login", "methods": ["POST"], "policy": "strict"}]
+      }"#).unwrap();
+      let policies = RateLimitPolicies::builtin(Quota::per_second(1, 10)).apply(config).unwrap();
+
+      assert_eq!(policies.resolve(&Method::POST, "/login"), ("strict", Some(Quota::per_minute(2, 2))));
+      assert_eq!(policies.resolve(&Method::POST, "/v1/accounts"), ("exempt", None));
+      assert_eq!(policies.resolve(&Method::GET, "/").0, "default");
+  }
+
+  #[test]
+  fn config_rejects_zero_quotas_and_unknown_policies() {
+      for config in [
+          r#"{"policies": {"strict": {"rate": 3, "period_seconds": 0, "burst": 3}}}"#,
+          r#"{"policies": {"strict": {"rate": 0, "period_seconds": 60, "burst": 3}}}"#,
+          r#"{"policies": {"strict": {"rate": 3, "period_seconds": 60, "burst": 0}}}"#,
+          r#"{"rules": [{"route": "/login", "policy": "unknown"}]}"#,
+      ] {
+          let parsed = serde_json::from_str(config).unwrap();
+          assert!(RateLimitPolicies::builtin(Quota::per_second(1, 10)).apply(parsed).is_err(), "{}", config);
+      }
+  }
+
+  #[test]
+  #[should_panic(expected = "Unknown rate limit policy")]
+  fn rules_must_reference_a_known_policy() {
+      RateLimitPolicies::new(Quota::per_second(1, 1)).with_route("/login", &[], "strict");
+  }
+}
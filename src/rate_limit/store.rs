@@ -0,0 +1,361 @@
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+use super::{Quota, RateLimitDecision};
+
+// How often buckets that have refilled completely are dropped.
+pub const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+// Keeps the theoretical arrival time of the next request for each client key. Implementations
+// must count a request atomically, since several workers and instances share the same keys.
+pub trait RateLimitStore: Send + Sync {
+  fn allow_query(&self, key: &str, quota: Quota) -> Result<RateLimitDecision, String>;
+}
+
+// Monotonic time source, injectable so that the limiter can be driven by tests.
+pub trait Clock: Send + Sync {
+  fn now(&self) -> Instant;
+}
+
+pub struct MonotonicClock;
+
+impl Clock for MonotonicClock {
+  fn now(&self) -> Instant {
+      Instant::now()
+  }
+}
+
+struct Buckets {
+  buckets: HashMap<String, Instant>,
+  last_eviction: Instant,
+}
+
+// Limits within this process only.
+pub struct MemoryStore {
+  clock: Arc<dyn Clock>,
+  buckets: Mutex<Buckets>,
+}
+
+impl MemoryStore {
+  pub fn new() -> Self {
+      MemoryStore::with_clock(Arc::new(MonotonicClock))
+  }
+
+  pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+      let now = clock.now();
+      MemoryStore {
+          clock,
+          buckets: Mutex::new(Buckets { buckets: HashMap::new(), last_eviction: now }),
+      }
+  }
+
+  // Generic cell rate algorithm: a request is accepted as long as the theoretical arrival time
+  // does not run more than the quota's tolerance ahead of now.
+  pub fn allow_query(&self, key: &str, quota: Quota) -> RateLimitDecision {
+      let now = self.clock.now();
+      let mut buckets = self.buckets.lock().unwrap();
+
+      // A bucket whose arrival time has passed is full again, so dropping it loses nothing.
+      if now.saturating_duration_since(buckets.last_eviction) >= EVICTION_INTERVAL {
+          buckets.buckets.retain(|_, tat| *tat > now);
+          buckets.last_eviction = now;
+      }
+
+      let tat = buckets.buckets.get(key).copied().unwrap_or(now).max(now);
+      let new_tat = tat + quota.emission_interval();
+      let wait = new_tat.saturating_duration_since(now);
+
+      let decision = RateLimitDecision::from_gcra(quota, wait);
+      if decision.allowed {
+          buckets.buckets.insert(key.to_string(), new_tat);
+      }
+      decision
+  }
+
+  #[cfg(test)]
+  pub(crate) fn len(&self) -> usize {
+      self.buckets.lock().unwrap().buckets.len()
+  }
+}
+
+impl Default for MemoryStore {
+  fn default() -> Self {
+    MemoryStore::new()
+  }
+}
+
+impl RateLimitStore for MemoryStore {
+  fn allow_query(&self, key: &str, quota: Quota) -> Result<RateLimitDecision, String> {
+      Ok(MemoryStore::allow_query(self, key, quota))
+  }
+}
+
+// Same algorithm as `MemoryStore`, run server side so that every instance shares the buckets.
+// Times are in microseconds and read from the server so that instance clocks do not matter.
+const GCRA_SCRIPT: &str = r"
+local interval = tonumber(ARGV[1])
+local tolerance = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000000 + tonumber(time[2])
+local tat = tonumber(redis.call('GET', KEYS[1])) or now
+if tat < now then
+  tat = now
+end
+local wait = tat + interval - now
+if wait <= tolerance then
+  redis.call('SET', KEYS[1], tat + interval, 'PX', math.ceil(wait / 1000))
+end
+return wait
+";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(200);
+const IO_TIMEOUT: Duration = Duration::from_millis(200);
+// After a failure the server is not contacted again before this delay.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+// Opens connections to the server, injectable so that failures can be simulated by tests.
+pub trait Connector: Send + Sync {
+  fn connect(&self) -> redis::RedisResult<redis::Connection>;
+}
+
+impl Connector for redis::Client {
+  fn connect(&self) -> redis::RedisResult<redis::Connection> {
+      let connection = self.get_connection_with_timeout(CONNECT_TIMEOUT)?;
+      connection.set_read_timeout(Some(IO_TIMEOUT))?;
+      connection.set_write_timeout(Some(IO_TIMEOUT))?;
+      Ok(connection)
+  }
+}
+
+struct Connections {
+  idle: Vec<redis::Connection>,
+  retry_at: Option<Instant>,
+}
+
+pub struct RedisStore {
+  connector: Box<dyn Connector>,
+  clock: Arc<dyn Clock>,
+  script: redis::Script,
+  prefix: String,
+  connections: Mutex<Connections>,
+}
+
+impl RedisStore {
+  pub fn new(url: &str) -> Result<Self, String> {
+      let client = redis::Client::open(url).map_err(|err| err.to_string())?;
+
+      Ok(RedisStore::with_connector(Box::new(client), Arc::new(MonotonicClock)))
+  }
+
+  pub fn with_connector(connector: Box<dyn Connector>, clock: Arc<dyn Clock>) -> Self {
+      RedisStore {
+          connector,
+          clock,
+          script: redis::Script::new(GCRA_SCRIPT),
+          prefix: String::from("rate_limit:"),
+          connections: Mutex::new(Connections { idle: Vec::new(), retry_at: None }),
+      }
+  }
+
+  fn connection(&self) -> Result<redis::Connection, String> {
+      {
+          let mut connections = self.connections.lock().unwrap();
+          if let Some(connection) = connections.idle.pop() {
+              return Ok(connection);
+          }
+          if connections.retry_at.is_some_and(|retry_at| self.clock.now() < retry_at) {
+              return Err(String::from("store unavailable, waiting before reconnecting"));
+          }
+      }
+
+      self.connector.connect().map_err(|err| self.failed(err))
+  }
+
+  fn failed(&self, err: redis::RedisError) -> String {
+      self.connections.lock().unwrap().retry_at = Some(self.clock.now() + RETRY_DELAY);
+      err.to_string()
+  }
+}
+
+impl RateLimitStore for RedisStore {
+  fn allow_query(&self, key: &str, quota: Quota) -> Result<RateLimitDecision, String> {
+      let mut connection = self.connection()?;
+
+      let wait: u64 = self.script
+          .key(format!("{}{}", self.prefix, key))
+          .arg(quota.emission_interval().as_micros() as u64)
+          .arg(quota.tolerance().as_micros() as u64)
+          .invoke(&mut connection)
+          .map_err(|err| self.failed(err))?;
+
+      // A connection that failed is dropped rather than returned to the pool.
+      let mut connections = self.connections.lock().unwrap();
+      connections.idle.push(connection);
+      connections.retry_at = None;
+
+      Ok(RateLimitDecision::from_gcra(quota, Duration::from_micros(wait)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  struct ManualClock(Mutex<Instant>);
+
+  impl ManualClock {
+      fn advance(&self, duration: Duration) {
+          *self.0.lock().unwrap() += duration;
+      }
+  }
+
+  impl Clock for ManualClock {
+      fn now(&self) -> Instant {
+          *self.0.lock().unwrap()
+      }
+  }
+
+  fn limiter(quota: Quota) -> (Limiter, Arc<ManualClock>) {
+      let clock = Arc::new(ManualClock(Mutex::new(Instant::now())));
+      (Limiter { store: MemoryStore::with_clock(clock.clone()), quota }, clock)
+  }
+
+  struct Limiter {
+      store: MemoryStore,
+      quota: Quota,
+  }
+
+  impl Limiter {
+      fn allow_query(&self, key: &str) -> RateLimitDecision {
+          self.store.allow_query(key, self.quota)
+      }
+  }
+
+  #[test]
+  fn a_new_client_gets_the_full_burst() {
+      let (limiter, _) = limiter(Quota::per_second(1, 5));
+
+      for remaining in (0..5).rev() {
+          let decision = limiter.allow_query("ip:a");
+          assert!(decision.allowed);
+          assert_eq!(decision.remaining, remaining);
+      }
+      assert!(!limiter.allow_query("ip:a").allowed);
+  }
+
+  #[test]
+  fn requests_are_replenished_at_the_sustained_rate() {
+      let (limiter, clock) = limiter(Quota::per_second(4, 2));
+      assert!(limiter.allow_query("ip:a").allowed);
+      assert!(limiter.allow_query("ip:a").allowed);
+      assert!(!limiter.allow_query("ip:a").allowed);
+
+      clock.advance(Duration::from_millis(200));
+      assert!(!limiter.allow_query("ip:a").allowed);
+
+      clock.advance(Duration::from_millis(50));
+      assert!(limiter.allow_query("ip:a").allowed);
+      assert!(!limiter.allow_query("ip:a").allowed);
+  }
+
+  #[test]
+  fn rejected_requests_report_when_to_retry() {
+      let (limiter, clock) = limiter(Quota::per_second(1, 1));
+      let accepted = limiter.allow_query("ip:a");
+      assert_eq!((accepted.remaining, accepted.reset), (0, 1));
+
+      clock.advance(Duration::from_millis(300));
+      let rejected = limiter.allow_query("ip:a");
+      assert!(!rejected.allowed);
+      assert_eq!(rejected.retry_after, 1);
+      assert_eq!(rejected.reset, 1);
+  }
+
+  #[test]
+  fn rejected_requests_do_not_consume_capacity() {
+      let (limiter, clock) = limiter(Quota::per_second(1, 1));
+      assert!(limiter.allow_query("ip:a").allowed);
+      for _ in 0..10 {
+          assert!(!limiter.allow_query("ip:a").allowed);
+      }
+
+      clock.advance(Duration::from_secs(1));
+      assert!(limiter.allow_query("ip:a").allowed);
+  }
+
+  #[test]
+  fn clients_are_limited_independently() {
+      let (limiter, _) = limiter(Quota::per_second(1, 1));
+      assert!(limiter.allow_query("ip:a").allowed);
+      assert!(!limiter.allow_query("ip:a").allowed);
+      assert!(limiter.allow_query("ip:b").allowed);
+  }
+
+  #[test]
+  fn idle_buckets_are_evicted_once_full() {
+      let (limiter, clock) = limiter(Quota::per_second(1, 2));
+      limiter.allow_query("ip:a");
+      limiter.allow_query("ip:b");
+
+      clock.advance(EVICTION_INTERVAL);
+      limiter.allow_query("ip:c");
+
+      let buckets = limiter.store.buckets.lock().unwrap();
+      assert_eq!(buckets.buckets.keys().collect::<Vec<_>>(), vec!["ip:c"]);
+  }
+
+  struct RefusingConnector(Arc<AtomicUsize>);
+
+  impl Connector for RefusingConnector {
+      fn connect(&self) -> redis::RedisResult<redis::Connection> {
+          self.0.fetch_add(1, Ordering::Relaxed);
+          Err(io::Error::from(io::ErrorKind::ConnectionRefused).into())
+      }
+  }
+
+  #[test]
+  fn unreachable_redis_is_not_dialed_again_before_the_retry_delay() {
+      let attempts = Arc::new(AtomicUsize::new(0));
+      let clock = Arc::new(ManualClock(Mutex::new(Instant::now())));
+      let store = RedisStore::with_connector(Box::new(RefusingConnector(attempts.clone())), clock.clone());
+      let quota = Quota::per_second(1, 1);
+
+      assert!(store.allow_query("ip:a", quota).is_err());
+      assert_eq!(attempts.load(Ordering::Relaxed), 1);
+
+      clock.advance(RETRY_DELAY - Duration::from_millis(1));
+      let err = store.allow_query("ip:a", quota).unwrap_err();
+      assert!(err.contains("waiting before reconnecting"));
+      assert_eq!(attempts.load(Ordering::Relaxed), 1);
+
+      clock.advance(Duration::from_millis(1));
+      assert!(store.allow_query("ip:a", quota).is_err());
+      assert_eq!(attempts.load(Ordering::Relaxed), 2);
+  }
+
+  // Runs against any redis-compatible server: `REDIS_URL=redis://127.0.0.1/ cargo test -- --ignored`.
+  #[test]
+  #[ignore]
+  fn redis_store_limits_like_the_memory_store() {
+      let url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+      let mut store = RedisStore::new(&url).unwrap();
+      store.prefix = format!("rate_limit_test:{}:", rand::random::<u64>());
+      let quota = Quota::per_minute(3, 3);
+
+      for remaining in (0..3).rev() {
+          let decision = store.allow_query("ip:a", quota).unwrap();
+          assert!(decision.allowed);
+          assert_eq!(decision.remaining, remaining);
+      }
+
+      let rejected = store.allow_query("ip:a", quota).unwrap();
+      assert!(!rejected.allowed);
+      assert!((19..=20).contains(&rejected.retry_after));
+      assert!(store.allow_query("ip:b", quota).unwrap().allowed);
+  }
+}
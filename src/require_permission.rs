@@ -0,0 +1,70 @@
+use std::future::{ready, Ready};
+
+use actix_web::{
+  body::EitherBody,
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  Error, HttpMessage,
+};
+use futures_util::{future::LocalBoxFuture, FutureExt, TryFutureExt};
+use crate::auth::AuthenticatedUser;
+use crate::errors::ActixError;
+
+// Must be wrapped inside `JwtCheck`, which provides the `AuthenticatedUser`.
+pub struct RequirePermission(pub &'static str);
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequirePermissionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+      ready(Ok(RequirePermissionMiddleware { service, permission: self.0 }))
+    }
+}
+
+
+pub struct RequirePermissionMiddleware<S> {
+  service: S,
+  permission: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = Error;
+  type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let allowed = req.extensions()
+      .get::<AuthenticatedUser>()
+      .map(|user| user.has_permission(self.permission));
+
+    let error = match allowed {
+      Some(true) => {
+        return self.service
+          .call(req)
+          .map_ok(ServiceResponse::map_into_left_body)
+          .boxed_local()
+      },
+      Some(false) => ActixError::Forbidden,
+      None => ActixError::UnAuthorized,
+    };
+
+    Box::pin(async move {
+      Ok(req.error_response(error).map_into_right_body())
+    })
+  }
+}
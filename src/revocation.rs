@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use actix_web::{rt, web};
+use chrono::Utc;
+use crate::persistance::Database;
+
+#[derive(Clone, Default)]
+pub struct RevocationList {
+    // access token -> timestamp after which the token is expired anyway and can be forgotten
+    tokens: Arc<RwLock<HashMap<String, i64>>>,
+}
+
+impl RevocationList {
+  pub fn new() -> RevocationList {
+      RevocationList::default()
+  }
+
+  pub fn revoke(&self, access_token: &str, expires_at: i64) {
+    self.extend(vec![(access_token.to_string(), expires_at)]);
+  }
+
+  pub fn extend(&self, revoked: Vec<(String, i64)>) {
+    let now = Utc::now().timestamp();
+    let mut tokens = self.tokens.write().unwrap();
+
+    tokens.retain(|_, expires_at| *expires_at > now);
+    tokens.extend(revoked.into_iter().filter(|(_, expires_at)| *expires_at > now));
+  }
+
+  pub fn is_revoked(&self, access_token: &str) -> bool {
+    self.tokens.read().unwrap().contains_key(access_token)
+  }
+
+  // Tokens revoked by rotation, reuse detection or another instance only reach this cache
+  // through the database, so it is re-synchronised periodically.
+  pub fn spawn_sync(&self, db: web::Data<dyn Database>, period: Duration) {
+    let revocations = self.clone();
+
+    rt::spawn(async move {
+      let mut interval = rt::time::interval(period);
+      interval.tick().await;
+
+      loop {
+        interval.tick().await;
+        let db = db.clone();
+        match web::block(move || db.get_revoked_access_tokens()).await {
+          Ok(Ok(tokens)) => revocations.extend(tokens),
+          Ok(Err(err)) => eprintln!("Erreur lors de la synchronisation des révocations: {}", err),
+          Err(err) => eprintln!("Erreur lors de la synchronisation des révocations: {}", err),
+        }
+      }
+    });
+  }
+}
@@ -0,0 +1,174 @@
+use std::env;
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use crate::errors::ActixError;
+use crate::models::{AccessToken, ChallengeClaims, Claims, RefreshClaims};
+use crate::password::random_token;
+use crate::persistance::Database;
+
+pub const ACCESS_TOKEN_LIFETIME_MINUTES: i64 = 10;
+const REFRESH_TOKEN_LIFETIME_DAYS: i64 = 7;
+const TWO_FACTOR_CHALLENGE_LIFETIME_MINUTES: i64 = 5;
+const TWO_FACTOR_AUDIENCE: &str = "2fa";
+
+// Secrets signing access tokens and 2FA challenges, and refresh tokens.
+#[derive(Clone)]
+pub struct TokenKeys {
+  token_secret: String,
+  refresh_token_secret: String,
+}
+
+impl TokenKeys {
+  pub fn new(token_secret: &str, refresh_token_secret: &str) -> Self {
+    TokenKeys {
+      token_secret: token_secret.to_string(),
+      refresh_token_secret: refresh_token_secret.to_string(),
+    }
+  }
+
+  // `TOKEN_SECRET` and `REFRESH_TOKEN_SECRET`, both required.
+  pub fn from_env() -> Result<Self, String> {
+    let read = |name: &str| env::var(name).map_err(|_| format!("{} doit être défini", name));
+
+    Ok(TokenKeys::new(&read("TOKEN_SECRET")?, &read("REFRESH_TOKEN_SECRET")?))
+  }
+
+  // Signed (access token, refresh token) of the account, carrying the roles and permissions it has now.
+  pub fn encode_token_pair(&self, id_account: u64, roles: Vec<String>, permissions: Vec<String>) -> Result<(String, String), ActixError> {
+    let claims = Claims {
+      id_account,
+      roles,
+      permissions,
+      exp: expiration(Duration::minutes(ACCESS_TOKEN_LIFETIME_MINUTES))?,
+      jti: random_token(16),
+    };
+    let refresh_claims = RefreshClaims {
+      id_account,
+      exp: expiration(Duration::days(REFRESH_TOKEN_LIFETIME_DAYS))?,
+      jti: random_token(16),
+    };
+
+    Ok((
+      encode(&Header::default(), &claims, &EncodingKey::from_secret(self.token_secret.as_ref())).map_err(|_| ActixError::Unknown)?,
+      encode(&Header::default(), &refresh_claims, &EncodingKey::from_secret(self.refresh_token_secret.as_ref())).map_err(|_| ActixError::Unknown)?,
+    ))
+  }
+
+  pub fn decode_access_token(&self, token: &str) -> Result<Claims, ActixError> {
+    let validation = Validation::new(Algorithm::HS256);
+    decode::<Claims>(token, &DecodingKey::from_secret(self.token_secret.as_ref()), &validation)
+      .map(|token_data| token_data.claims)
+      .map_err(|_| ActixError::UnAuthorized)
+  }
+
+  // Only checks the signature and expiry; whether the token was used already is up to the database.
+  pub fn decode_refresh_token(&self, refresh_token: &str) -> Result<(), ActixError> {
+    let validation = Validation::new(Algorithm::HS256);
+    decode::<RefreshClaims>(refresh_token, &DecodingKey::from_secret(self.refresh_token_secret.as_ref()), &validation)
+      .map(|_| ())
+      .map_err(|_| ActixError::UnAuthorized)
+  }
+
+  // Short-lived proof that the password step of a login succeeded. The audience keeps it from
+  // being accepted as an access token.
+  pub fn create_two_factor_challenge(&self, id_account: u64) -> Result<String, ActixError> {
+    let claims = ChallengeClaims {
+      id_account,
+      aud: String::from(TWO_FACTOR_AUDIENCE),
+      exp: expiration(Duration::minutes(TWO_FACTOR_CHALLENGE_LIFETIME_MINUTES))?,
+    };
+
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(self.token_secret.as_ref())).map_err(|_| ActixError::Unknown)
+  }
+
+  pub fn decode_two_factor_challenge(&self, challenge_token: &str) -> Result<u64, ActixError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_audience(&[TWO_FACTOR_AUDIENCE]);
+
+    decode::<ChallengeClaims>(challenge_token, &DecodingKey::from_secret(self.token_secret.as_ref()), &validation)
+      .map(|token_data| token_data.claims.id_account)
+      .map_err(|_| ActixError::UnAuthorized)
+  }
+}
+
+// Starts a new family of tokens, at login.
+pub fn create_access_token(db: &dyn Database, keys: &TokenKeys, id_account: u64) -> Result<AccessToken, ActixError> {
+  issue_token_pair(db, keys, id_account, &random_token(32))
+}
+
+// Refresh tokens are single-use: seeing one again means it leaked, so its whole family goes.
+pub fn refresh_access_token(db: &dyn Database, keys: &TokenKeys, refresh_token: &str) -> Result<AccessToken, ActixError> {
+  keys.decode_refresh_token(refresh_token)?;
+
+  let (id, id_account, family, revoked) = db.get_refresh_token(refresh_token)?
+    .ok_or(ActixError::UnAuthorized)?;
+
+  if revoked || !db.revoke_token_pair(id)? {
+    db.revoke_token_family(&family)?;
+    return Err(ActixError::UnAuthorized);
+  }
+
+  issue_token_pair(db, keys, id_account, &family)
+}
+
+fn issue_token_pair(db: &dyn Database, keys: &TokenKeys, id_account: u64, family: &str) -> Result<AccessToken, ActixError> {
+  let (roles, permissions) = db.get_account_authorizations(id_account)?;
+  let (access_token, refresh_token) = keys.encode_token_pair(id_account, roles, permissions)?;
+
+  db.insert_access_token(id_account, &access_token, &refresh_token, family)
+}
+
+fn expiration(lifetime: Duration) -> Result<i64, ActixError> {
+  Ok(Utc::now().checked_add_signed(lifetime)
+    .ok_or(ActixError::Unknown)?
+    .timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::accounts::AccountStore;
+  use crate::migrations::{run, MigrateArgs, MigrateCommand};
+  use crate::persistance::SqliteDatabase;
+
+  fn keys() -> TokenKeys {
+    TokenKeys::new("token_secret", "refresh_token_secret")
+  }
+
+  #[test]
+  fn access_tokens_carry_the_authorizations_of_their_account() {
+    let db = SqliteDatabase::open(":memory:").unwrap();
+    run(&db, &MigrateArgs { command: MigrateCommand::Up, dry_run: false }).unwrap();
+    let account = db.create("alice", "hash").unwrap();
+
+    let token = create_access_token(&db, &keys(), account.id).unwrap();
+    let claims = keys().decode_access_token(&token.access_token).unwrap();
+    assert_eq!((claims.id_account, claims.roles), (account.id, vec![String::from("user")]));
+    assert!(TokenKeys::new("other_secret", "refresh_token_secret").decode_access_token(&token.access_token).is_err());
+  }
+
+  #[test]
+  fn reused_refresh_token_revokes_the_family() {
+    let db = SqliteDatabase::open(":memory:").unwrap();
+    run(&db, &MigrateArgs { command: MigrateCommand::Up, dry_run: false }).unwrap();
+    let account = db.create("alice", "hash").unwrap();
+
+    let first = create_access_token(&db, &keys(), account.id).unwrap();
+    let second = refresh_access_token(&db, &keys(), &first.refresh_token).unwrap();
+    assert!(refresh_access_token(&db, &keys(), &first.refresh_token).is_err());
+    // The reuse revoked the token issued by the legitimate refresh as well.
+    assert!(refresh_access_token(&db, &keys(), &second.refresh_token).is_err());
+
+    let revoked: Vec<String> = db.get_revoked_access_tokens().unwrap().into_iter().map(|(token, _)| token).collect();
+    assert!(revoked.contains(&first.access_token));
+    assert!(revoked.contains(&second.access_token));
+  }
+
+  #[test]
+  fn two_factor_challenges_are_not_access_tokens() {
+    let challenge = keys().create_two_factor_challenge(7).unwrap();
+
+    assert_eq!(keys().decode_two_factor_challenge(&challenge).unwrap(), 7);
+    assert!(keys().decode_access_token(&challenge).is_err());
+  }
+}
@@ -0,0 +1,120 @@
+use base32::Alphabet;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use crate::password::constant_time_eq;
+
+// RFC 6238 defaults, which is what authenticator apps expect.
+const STEP_SECONDS: u64 = 30;
+const DIGITS: usize = 6;
+const ALLOWED_SKEW: u64 = 1;
+const SECRET_ALPHABET: Alphabet = Alphabet::RFC4648 { padding: false };
+
+pub fn generate_secret() -> String {
+  let bytes: [u8; 20] = rand::random();
+  base32::encode(SECRET_ALPHABET, &bytes)
+}
+
+pub fn is_totp_code(code: &str) -> bool {
+  code.len() == DIGITS && code.chars().all(|c| c.is_ascii_digit())
+}
+
+// Returns the time step matched by `code` so that callers can refuse to accept it twice.
+pub fn verify(secret: &str, code: &str, unix_time: u64) -> Option<u64> {
+  if !is_totp_code(code) {
+    return None;
+  }
+  let key = base32::decode(SECRET_ALPHABET, secret)?;
+
+  let current_step = unix_time / STEP_SECONDS;
+  (current_step.saturating_sub(ALLOWED_SKEW)..=current_step + ALLOWED_SKEW)
+    .find(|step| constant_time_eq(format_code(hotp(&key, *step)).as_bytes(), code.as_bytes()))
+}
+
+#[cfg(test)]
+pub(crate) fn code_at(secret: &str, unix_time: u64) -> String {
+  let key = base32::decode(SECRET_ALPHABET, secret).unwrap();
+  format_code(hotp(&key, unix_time / STEP_SECONDS))
+}
+
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+  format!(
+    "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+    encode_component(issuer),
+    encode_component(account),
+    secret,
+    encode_component(issuer),
+    DIGITS,
+    STEP_SECONDS,
+  )
+}
+
+fn hotp(key: &[u8], counter: u64) -> u32 {
+  let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any size");
+  mac.update(&counter.to_be_bytes());
+  let digest = mac.finalize().into_bytes();
+
+  let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+  let binary = u32::from_be_bytes([
+    digest[offset] & 0x7f,
+    digest[offset + 1],
+    digest[offset + 2],
+    digest[offset + 3],
+  ]);
+
+  binary % 10u32.pow(DIGITS as u32)
+}
+
+fn format_code(code: u32) -> String {
+  format!("{:0width$}", code, width = DIGITS)
+}
+
+fn encode_component(value: &str) -> String {
+  value.bytes()
+    .map(|byte| match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+      _ => format!("%{:02X}", byte),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Key of the RFC 6238 SHA-1 test vectors, "12345678901234567890".
+  const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+  #[test]
+  fn codes_match_the_rfc_6238_vectors() {
+    // The RFC lists 8 digits; 6-digit codes are their last 6.
+    for (unix_time, code) in [
+      (59, "287082"),
+      (1111111109, "081804"),
+      (1111111111, "050471"),
+      (1234567890, "005924"),
+      (2000000000, "279037"),
+      (20000000000, "353130"),
+    ] {
+      assert_eq!(code_at(RFC_SECRET, unix_time), code);
+      assert_eq!(verify(RFC_SECRET, code, unix_time), Some(unix_time / STEP_SECONDS));
+    }
+  }
+
+  #[test]
+  fn codes_are_accepted_one_step_early_or_late() {
+    let step = 1111111109 / STEP_SECONDS;
+
+    assert_eq!(verify(RFC_SECRET, "081804", 1111111109 - STEP_SECONDS), Some(step));
+    assert_eq!(verify(RFC_SECRET, "081804", 1111111109 + STEP_SECONDS), Some(step));
+    assert_eq!(verify(RFC_SECRET, "081804", 1111111109 - 2 * STEP_SECONDS), None);
+    assert_eq!(verify(RFC_SECRET, "081804", 1111111109 + 2 * STEP_SECONDS), None);
+  }
+
+  #[test]
+  fn malformed_codes_and_secrets_are_refused() {
+    assert_eq!(verify(RFC_SECRET, "28708", 59), None);
+    assert_eq!(verify(RFC_SECRET, "2870821", 59), None);
+    assert_eq!(verify(RFC_SECRET, "28708a", 59), None);
+    assert_eq!(verify("not base32!", "287082", 59), None);
+  }
+}